@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS gift_orders (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    package_name TEXT NOT NULL,
+    authors TEXT[] NOT NULL DEFAULT '{}',
+    format TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS gift_order_items (
+    order_id UUID NOT NULL REFERENCES gift_orders (id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    item TEXT NOT NULL,
+    quantity BIGINT NOT NULL,
+    PRIMARY KEY (order_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS gift_orders_authors ON gift_orders USING GIN (authors);
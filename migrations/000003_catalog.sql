@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS catalog (
+    item TEXT PRIMARY KEY,
+    stock BIGINT NOT NULL CHECK (stock >= 0)
+);
+
+ALTER TABLE gift_order_items ADD COLUMN IF NOT EXISTS status TEXT;
+ALTER TABLE gift_order_items ADD COLUMN IF NOT EXISTS fulfilled BIGINT;
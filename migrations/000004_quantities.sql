@@ -0,0 +1,5 @@
+ALTER TABLE gift_order_items ALTER COLUMN quantity TYPE NUMERIC;
+ALTER TABLE gift_order_items ALTER COLUMN fulfilled TYPE NUMERIC;
+ALTER TABLE gift_order_items ADD COLUMN IF NOT EXISTS unit TEXT;
+
+ALTER TABLE catalog ALTER COLUMN stock TYPE NUMERIC;
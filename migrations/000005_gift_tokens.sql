@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS gift_tokens (
+    jti TEXT PRIMARY KEY,
+    subject TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS gift_tokens_subject ON gift_tokens (subject);
+CREATE INDEX IF NOT EXISTS gift_tokens_expires_at ON gift_tokens (expires_at);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS gift_refresh_tokens (
+    hash TEXT PRIMARY KEY,
+    family UUID NOT NULL,
+    subject TEXT,
+    gift JSONB NOT NULL,
+    encrypted BOOLEAN NOT NULL DEFAULT false,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS gift_refresh_tokens_family ON gift_refresh_tokens (family);
+CREATE INDEX IF NOT EXISTS gift_refresh_tokens_subject ON gift_refresh_tokens (subject);
+CREATE INDEX IF NOT EXISTS gift_refresh_tokens_expires_at ON gift_refresh_tokens (expires_at);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS anonymizer_tenants (
+    tenant TEXT PRIMARY KEY,
+    token_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS anonymizer_keys (
+    tenant TEXT NOT NULL REFERENCES anonymizer_tenants (tenant) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    key BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tenant, version)
+);
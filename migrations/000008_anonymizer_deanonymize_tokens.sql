@@ -0,0 +1,2 @@
+-- Recovering addresses needs its own token, tenants created before it can't deanonymize
+ALTER TABLE anonymizer_tenants ADD COLUMN IF NOT EXISTS deanonymize_token_hash TEXT;
@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use tracing::{debug, info, warn};
+
+use super::{Format, GiftOrderState, LineStatus, OrderLine, Quantity};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub(super) struct CatalogItem {
+    pub(super) item: String,
+    /// In the item's normalised unit
+    pub(super) stock: Quantity,
+}
+
+/// Reserve stock for every line, locking the catalog rows until the transaction finishes
+pub(super) async fn reserve(
+    connection: &mut PgConnection,
+    lines: &mut [OrderLine],
+) -> Result<(), sqlx::Error> {
+    let items = lines
+        .iter()
+        .map(|line| line.item.clone())
+        .collect::<Vec<_>>();
+
+    // Lock in a consistent order so concurrent orders can't deadlock
+    let mut stock = sqlx::query_as!(
+        CatalogItem,
+        r#"SELECT item, stock as "stock: Quantity" FROM catalog WHERE item = ANY($1) ORDER BY item FOR UPDATE"#,
+        &items
+    )
+    .fetch_all(&mut *connection)
+    .await?
+    .into_iter()
+    .map(|row| (row.item, row.stock))
+    .collect::<HashMap<_, _>>();
+    debug!(?stock);
+
+    for line in lines.iter_mut() {
+        let Some(available) = stock.get_mut(&line.item) else {
+            line.status = Some(LineStatus::Rejected);
+            line.fulfilled = Some(Quantity::ZERO);
+            continue;
+        };
+
+        let taken = (*available).min(line.quantity);
+        *available = available.checked_sub(taken).unwrap_or_default();
+        line.fulfilled = Some(taken);
+        line.status = Some(if taken == line.quantity {
+            LineStatus::Fulfilled
+        } else {
+            LineStatus::Backordered
+        });
+
+        sqlx::query!(
+            r#"UPDATE catalog SET stock = stock - $1 WHERE item = $2"#,
+            taken.0,
+            line.item
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    info!(?lines, "Reserved stock");
+    Ok(())
+}
+
+async fn list(pool: &sqlx::PgPool) -> Result<Vec<CatalogItem>, sqlx::Error> {
+    sqlx::query_as!(
+        CatalogItem,
+        r#"SELECT item, stock as "stock: Quantity" FROM catalog ORDER BY item"#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub(super) async fn catalog(State(state): State<GiftOrderState>) -> Response {
+    match list(&state.pool).await {
+        Ok(items) => Json(items).into_response(),
+        Err(err) => {
+            warn!(?err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Set the stock of each item, e.g. `"Toy car" = 10` or `Milk = 2.5` (liters) in toml. Needs the
+/// admin bearer token, without one configured nobody can restock.
+pub(super) async fn stock(
+    State(state): State<GiftOrderState>,
+    headers: HeaderMap,
+    data: String,
+) -> Response {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+    if state.admin_token.is_none() || bearer != state.admin_token.as_deref() {
+        warn!("Unauthorized restock");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Some(format) = Format::from_headers(&headers) else {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    };
+    let stock = match format.deserialize::<BTreeMap<String, Quantity>>(&data) {
+        Ok(stock) => stock,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    info!(?stock, "Stocking catalog");
+
+    let (items, counts): (Vec<_>, Vec<_>) = stock
+        .into_iter()
+        .map(|(item, count)| (item, count.0))
+        .unzip();
+    let result = sqlx::query!(
+        r#"
+    INSERT INTO catalog (item, stock)
+        SELECT * FROM UNNEST($1::TEXT[], $2::NUMERIC[])
+        ON CONFLICT (item) DO UPDATE SET stock = EXCLUDED.stock
+    "#,
+        &items,
+        &counts
+    )
+    .execute(&state.pool)
+    .await;
+
+    match result {
+        Ok(_) => catalog(State(state)).await,
+        Err(err) => {
+            warn!(?err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Multipart, Query},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::{
+    format::{read_fields, Manifests, Output, Upload},
+    quantity::Unit,
+    workspace::Workspace,
+    OrderLine, Quantity, ResponseFormat, Summary,
+};
+
+/// The parts of a manifest that are compared
+#[derive(Debug, Default)]
+struct Snapshot {
+    orders: Vec<OrderLine>,
+    keywords: Vec<String>,
+    /// Every other `[package]` field
+    fields: BTreeMap<String, Value>,
+}
+
+impl Snapshot {
+    fn parse(upload: Upload) -> Result<Snapshot, String> {
+        let manifests = Manifests {
+            manifest: upload,
+            members: vec![],
+        };
+        let workspace = Workspace::parse(&manifests)?;
+        let resolved = workspace.resolve();
+        let orders = Summary::from_orders(workspace.orders(&resolved), false, &BTreeMap::new())
+            .map_err(|err| err.to_text())?
+            .orders;
+
+        let Some(package) = resolved.into_iter().find_map(|manifest| manifest.package) else {
+            return Ok(Snapshot {
+                orders,
+                ..Default::default()
+            });
+        };
+
+        let keywords = package
+            .keywords
+            .clone()
+            .and_then(|keywords| keywords.as_local())
+            .unwrap_or_default();
+        let mut fields = match serde_json::to_value(package).map_err(|err| err.to_string())? {
+            Value::Object(fields) => fields.into_iter().collect::<BTreeMap<_, _>>(),
+            _ => BTreeMap::new(),
+        };
+        fields.remove("keywords");
+        fields.remove("metadata");
+
+        Ok(Snapshot {
+            orders,
+            keywords,
+            fields,
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    fn of<T: PartialEq>(old: Option<&T>, new: Option<&T>) -> Option<Change> {
+        match (old, new) {
+            (None, Some(_)) => Some(Change::Added),
+            (Some(_), None) => Some(Change::Removed),
+            (Some(old), Some(new)) if old != new => Some(Change::Changed),
+            _ => None,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Change::Added => '+',
+            Change::Removed => '-',
+            Change::Changed => '~',
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+struct Amount {
+    quantity: Quantity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<Unit>,
+}
+
+impl Amount {
+    fn to_text(&self) -> String {
+        match self.unit {
+            Some(unit) => format!("{} {}", self.quantity, unit.as_str()),
+            None => self.quantity.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+struct ItemChange {
+    item: String,
+    change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new: Option<Amount>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+struct FieldChange {
+    field: String,
+    change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new: Option<Value>,
+}
+
+#[derive(Serialize, PartialEq, Debug, Default)]
+struct KeywordChanges {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+struct Diff {
+    items: Vec<ItemChange>,
+    keywords: KeywordChanges,
+    package: Vec<FieldChange>,
+}
+
+impl Diff {
+    fn new(old: Snapshot, new: Snapshot) -> Diff {
+        let amounts = |orders: Vec<OrderLine>| {
+            orders
+                .into_iter()
+                .map(|line| {
+                    let amount = Amount {
+                        quantity: line.quantity,
+                        unit: line.unit,
+                    };
+                    (line.item, amount)
+                })
+                .collect::<IndexMap<_, _>>()
+        };
+        let (mut old_items, mut new_items) = (amounts(old.orders), amounts(new.orders));
+        let names = old_items
+            .keys()
+            .chain(
+                new_items
+                    .keys()
+                    .filter(|item| !old_items.contains_key(*item)),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        let items = names
+            .into_iter()
+            .filter_map(|item| {
+                let (old, new) = (old_items.swap_remove(&item), new_items.swap_remove(&item));
+                let change = Change::of(old.as_ref(), new.as_ref())?;
+                Some(ItemChange {
+                    item,
+                    change,
+                    old,
+                    new,
+                })
+            })
+            .collect();
+
+        let keywords = KeywordChanges {
+            added: difference(&new.keywords, &old.keywords),
+            removed: difference(&old.keywords, &new.keywords),
+        };
+
+        let (mut old_fields, mut new_fields) = (old.fields, new.fields);
+        let mut names = old_fields.keys().cloned().collect::<Vec<_>>();
+        names.extend(new_fields.keys().cloned());
+        names.sort();
+        names.dedup();
+        let package = names
+            .into_iter()
+            .filter_map(|field| {
+                let (old, new) = (old_fields.remove(&field), new_fields.remove(&field));
+                let change = Change::of(old.as_ref(), new.as_ref())?;
+                Some(FieldChange {
+                    field,
+                    change,
+                    old,
+                    new,
+                })
+            })
+            .collect();
+
+        Diff {
+            items,
+            keywords,
+            package,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let items = self.items.iter().map(|change| {
+            let amount = match (&change.old, &change.new) {
+                (Some(old), Some(new)) => format!("{} -> {}", old.to_text(), new.to_text()),
+                (Some(amount), None) | (None, Some(amount)) => amount.to_text(),
+                (None, None) => String::new(),
+            };
+            format!("{} {}: {amount}", change.change.symbol(), change.item)
+        });
+        let keywords = self
+            .keywords
+            .added
+            .iter()
+            .map(|keyword| format!("+ keyword {keyword}"))
+            .chain(
+                self.keywords
+                    .removed
+                    .iter()
+                    .map(|keyword| format!("- keyword {keyword}")),
+            );
+        let fields = self.package.iter().map(|change| {
+            let value = match (&change.old, &change.new) {
+                (Some(old), Some(new)) => format!("{old} -> {new}"),
+                (Some(value), None) | (None, Some(value)) => value.to_string(),
+                (None, None) => String::new(),
+            };
+            format!(
+                "{} package.{}: {value}",
+                change.change.symbol(),
+                change.field
+            )
+        });
+
+        items
+            .chain(keywords)
+            .chain(fields)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Items of `from` missing in `other`, in `from`'s order
+fn difference(from: &[String], other: &[String]) -> Vec<String> {
+    from.iter()
+        .filter(|value| !other.contains(value))
+        .cloned()
+        .collect()
+}
+
+#[derive(Deserialize, Debug)]
+pub(super) struct DiffOptions {
+    format: Option<ResponseFormat>,
+}
+
+/// Compare the `old` and `new` manifest fields of a multipart upload
+pub(super) async fn diff(
+    Query(options): Query<DiffOptions>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Response {
+    let mut fields = match read_fields(&mut multipart).await {
+        Ok(fields) => fields.into_iter().collect::<BTreeMap<_, _>>(),
+        Err(status) => return status.into_response(),
+    };
+    let (Some(old), Some(new)) = (fields.remove("old"), fields.remove("new")) else {
+        return (StatusCode::BAD_REQUEST, "Missing `old` or `new` manifest").into_response();
+    };
+
+    let snapshots = Snapshot::parse(old).and_then(|old| Ok((old, Snapshot::parse(new)?)));
+    let diff = match snapshots {
+        Ok((old, new)) => Diff::new(old, new),
+        Err(err) => {
+            warn!(?err, "Bad manifest");
+            return (StatusCode::BAD_REQUEST, "Invalid manifest").into_response();
+        }
+    };
+    info!(?diff);
+
+    let json = match options.format {
+        Some(format) => format == ResponseFormat::Json,
+        None => match Output::negotiate(&headers, &[Output::Text, Output::Json]) {
+            Ok(output) => output == Output::Json,
+            Err(status) => return status.into_response(),
+        },
+    };
+    if json {
+        Json(diff).into_response()
+    } else {
+        diff.to_text().into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::format::Format;
+    use super::*;
+
+    fn snapshot(data: &str) -> Snapshot {
+        Snapshot::parse(Upload {
+            format: Format::Toml,
+            data: data.to_string(),
+        })
+        .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_diff() {
+        let old = snapshot(
+            r#"
+[package]
+name = "gift"
+license = "MIT"
+keywords = ["Christmas 2024", "toys"]
+
+[package.metadata]
+orders = [
+    { item = "Toy car", quantity = 2 },
+    { item = "Coal", quantity = 1 },
+    { item = "Milk", quantity = 1, unit = "l" },
+]
+"#,
+        );
+        let new = snapshot(
+            r#"
+[package]
+name = "gift"
+description = "Presents"
+keywords = ["Christmas 2024", "sweets"]
+
+[package.metadata]
+orders = [
+    { item = "Milk", quantity = 1500, unit = "ml" },
+    { item = "Toy car", quantity = 2 },
+    { item = "Candy cane", quantity = 10 },
+]
+"#,
+        );
+
+        let diff = Diff::new(old, new);
+
+        assert_eq!(
+            r#"- Coal: 1
+~ Milk: 1 liters -> 1.5 liters
++ Candy cane: 10
++ keyword sweets
+- keyword toys
++ package.description: "Presents"
+- package.license: "MIT""#,
+            diff.to_text()
+        );
+    }
+}
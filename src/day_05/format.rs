@@ -0,0 +1,387 @@
+use axum::{
+    extract::{FromRequest, Multipart, Request},
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+};
+use mime::Mime;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+
+use super::{OrderLine, Quantity, Summary};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Format {
+    Toml,
+    Json,
+    Yaml,
+    Json5,
+    Ron,
+}
+
+impl Format {
+    /// Parse a media type such as `application/vnd.cargo+json; charset=utf-8`
+    pub(super) fn from_media_type(media_type: &str) -> Option<Format> {
+        let mime = media_type.parse::<Mime>().ok()?;
+        if let Some(charset) = mime.get_param(mime::CHARSET) {
+            if !charset.as_str().eq_ignore_ascii_case("utf-8") {
+                return None;
+            }
+        }
+
+        let suffix = mime.suffix().map(|suffix| suffix.as_str());
+        match (mime.type_().as_str(), mime.subtype().as_str(), suffix) {
+            ("application" | "text", "toml" | "x-toml", None) => Some(Format::Toml),
+            ("application" | "text", "json" | "x-json", None) => Some(Format::Json),
+            ("application" | "text", "yaml" | "x-yaml", None) => Some(Format::Yaml),
+            ("application" | "text", "json5", None) => Some(Format::Json5),
+            ("application" | "text", "ron" | "x-ron", None) => Some(Format::Ron),
+            ("application", _, Some("toml")) => Some(Format::Toml),
+            ("application", _, Some("json")) => Some(Format::Json),
+            ("application", _, Some("yaml")) => Some(Format::Yaml),
+            ("application", _, Some("json5")) => Some(Format::Json5),
+            _ => None,
+        }
+    }
+
+    pub(super) fn from_headers(headers: &HeaderMap) -> Option<Format> {
+        headers
+            .get(CONTENT_TYPE)
+            .and_then(|header| header.to_str().ok())
+            .and_then(Format::from_media_type)
+    }
+
+    pub(super) fn from_file_name(name: &str) -> Option<Format> {
+        match name.rsplit_once('.')?.1.to_lowercase().as_str() {
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "json5" => Some(Format::Json5),
+            "ron" => Some(Format::Ron),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Json5 => "json5",
+            Format::Ron => "ron",
+        }
+    }
+
+    pub(super) fn deserialize<T: DeserializeOwned>(&self, data: &str) -> Result<T, String> {
+        match self {
+            Format::Toml => toml::from_str(data).map_err(|err| err.to_string()),
+            Format::Json => serde_json::from_str(data).map_err(|err| err.to_string()),
+            Format::Yaml => serde_yaml::from_str(data).map_err(|err| err.to_string()),
+            Format::Json5 => json5::from_str(data).map_err(|err| err.to_string()),
+            Format::Ron => ron_options().from_str(data).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Manifest fields are mostly optional, so don't require them to be wrapped in `Some(..)`
+pub(super) fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+}
+
+/// Representations of the order lines that can be requested through `Accept`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(super) enum Output {
+    /// The order summary as text, including strict mode rejections
+    Text,
+    Json,
+    Csv,
+    Yaml,
+    Toml,
+}
+
+impl Output {
+    /// Everything the order summary can be rendered as, the first one is the default
+    pub(super) const ALL: [Output; 5] = [
+        Output::Text,
+        Output::Json,
+        Output::Csv,
+        Output::Yaml,
+        Output::Toml,
+    ];
+
+    fn from_media_type(media_type: &str) -> Option<Output> {
+        let essence = media_type
+            .parse::<Mime>()
+            .map(|mime| mime.essence_str().to_lowercase());
+        match essence.as_deref() {
+            Ok("text/csv") => return Some(Output::Csv),
+            Ok("text/plain" | "text/*") => return Some(Output::Text),
+            _ => {}
+        }
+
+        match Format::from_media_type(media_type)? {
+            Format::Json => Some(Output::Json),
+            Format::Yaml => Some(Output::Yaml),
+            Format::Toml => Some(Output::Toml),
+            Format::Json5 | Format::Ron => None,
+        }
+    }
+
+    /// The listed media type in `supported` with the highest `q`, the first one listed wins
+    /// ties and `*/*` is the first of `supported`. 406 when nothing listed is supported.
+    pub(super) fn negotiate(
+        headers: &HeaderMap,
+        supported: &[Output],
+    ) -> Result<Output, StatusCode> {
+        let Some(accept) = headers.get(ACCEPT).and_then(|accept| accept.to_str().ok()) else {
+            return Ok(supported[0]);
+        };
+
+        let mut best: Option<(f32, Output)> = None;
+        for media_type in accept.split(',').map(str::trim) {
+            let Ok(mime) = media_type.parse::<Mime>() else {
+                continue;
+            };
+            let quality = mime
+                .get_param("q")
+                .and_then(|quality| quality.as_str().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let output = match mime.essence_str() {
+                "*/*" => Some(supported[0]),
+                _ => Output::from_media_type(media_type),
+            };
+            let Some(output) = output.filter(|output| supported.contains(output)) else {
+                continue;
+            };
+
+            if quality > 0.0 && best.is_none_or(|(best, _)| quality > best) {
+                best = Some((quality, output));
+            }
+        }
+
+        best.map(|(_, output)| output).ok_or_else(|| {
+            warn!(?accept, "No acceptable output");
+            StatusCode::NOT_ACCEPTABLE
+        })
+    }
+
+    pub(super) fn content_type(&self) -> &'static str {
+        match self {
+            Output::Text => "text/plain; charset=utf-8",
+            Output::Json => "application/json",
+            Output::Csv => "text/csv; charset=utf-8",
+            Output::Yaml => "application/yaml",
+            Output::Toml => "application/toml",
+        }
+    }
+
+    pub(super) fn serialize(&self, summary: &Summary) -> Result<String, String> {
+        let lines = &summary.orders;
+        match self {
+            Output::Text => Ok(summary.to_text()),
+            Output::Json => serde_json::to_string(lines).map_err(|err| err.to_string()),
+            Output::Yaml => serde_yaml::to_string(lines).map_err(|err| err.to_string()),
+            // toml documents can't be a bare array
+            Output::Toml => {
+                toml::to_string(&Orders { orders: lines }).map_err(|err| err.to_string())
+            }
+            Output::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                for line in lines {
+                    writer
+                        .serialize(CsvLine {
+                            item: &line.item,
+                            quantity: line.quantity,
+                            unit: line.unit.map(|unit| unit.as_str()),
+                            status: line.status.map(|status| status.as_str()),
+                            fulfilled: line.fulfilled,
+                        })
+                        .map_err(|err| err.to_string())?;
+                }
+
+                let data = writer.into_inner().map_err(|err| err.to_string())?;
+                String::from_utf8(data).map_err(|err| err.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Orders<'a> {
+    orders: &'a [OrderLine],
+}
+
+/// Every column is always written so rows line up, unlike the other formats
+#[derive(Serialize)]
+struct CsvLine<'a> {
+    item: &'a str,
+    quantity: Quantity,
+    unit: Option<&'a str>,
+    status: Option<&'a str>,
+    fulfilled: Option<Quantity>,
+}
+
+fn is_multipart(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.parse::<Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == mime::MULTIPART_FORM_DATA.essence_str())
+}
+
+/// A file read from a body or multipart field
+pub(super) struct Upload {
+    pub(super) format: Format,
+    pub(super) data: String,
+}
+
+/// Read every file field as `(name, upload)`, the format comes from the field's content type or
+/// file name and otherwise defaults to toml like a `Cargo.toml`
+pub(super) async fn read_fields(
+    multipart: &mut Multipart,
+) -> Result<Vec<(String, Upload)>, StatusCode> {
+    let mut fields = vec![];
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+    {
+        let Some(name) = field.name().map(str::to_string) else {
+            continue;
+        };
+
+        let format = field
+            .content_type()
+            .and_then(Format::from_media_type)
+            .or_else(|| field.file_name().and_then(Format::from_file_name))
+            .unwrap_or(Format::Toml);
+        let data = field.text().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+        info!(?name, ?format);
+
+        fields.push((name, Upload { format, data }));
+    }
+
+    Ok(fields)
+}
+
+/// Manifests submitted together, a workspace root can be uploaded with its members as repeated
+/// `member` fields next to the `manifest` field
+pub(super) struct Manifests {
+    pub(super) manifest: Upload,
+    pub(super) members: Vec<Upload>,
+}
+
+/// Read manifests from the body, or from the `manifest` and `member` fields of a multipart upload
+pub(super) async fn read_manifests(request: Request) -> Result<Manifests, StatusCode> {
+    if is_multipart(request.headers()) {
+        let mut multipart = Multipart::from_request(request, &())
+            .await
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        let mut manifest = None;
+        let mut members = vec![];
+        for (name, upload) in read_fields(&mut multipart).await? {
+            match name.as_str() {
+                "manifest" if manifest.is_none() => manifest = Some(upload),
+                "member" => members.push(upload),
+                _ => warn!(?name, "Ignoring field"),
+            }
+        }
+
+        let manifest = manifest.ok_or_else(|| {
+            warn!("Missing manifest field");
+            StatusCode::BAD_REQUEST
+        })?;
+        return Ok(Manifests { manifest, members });
+    }
+
+    let format =
+        Format::from_headers(request.headers()).ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+    let data = String::from_request(request, &())
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Manifests {
+        manifest: Upload { format, data },
+        members: vec![],
+    })
+}
+
+/// Read a single manifest, see [`read_manifests`]
+pub(super) async fn read_manifest(request: Request) -> Result<(Format, String), StatusCode> {
+    let Manifests { manifest, .. } = read_manifests(request).await?;
+
+    Ok((manifest.format, manifest.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case("application/toml", Some(Format::Toml))]
+    #[case("application/toml; charset=utf-8", Some(Format::Toml))]
+    #[case("Application/TOML; charset=UTF-8", Some(Format::Toml))]
+    #[case("text/x-toml", Some(Format::Toml))]
+    #[case("application/json", Some(Format::Json))]
+    #[case("application/vnd.cargo+json", Some(Format::Json))]
+    #[case("text/yaml", Some(Format::Yaml))]
+    #[case("application/x-yaml", Some(Format::Yaml))]
+    #[case("application/json5", Some(Format::Json5))]
+    #[case("application/ron", Some(Format::Ron))]
+    #[case("application/json; charset=latin1", None)]
+    #[case("application/html", None)]
+    #[case("not a media type", None)]
+    #[test_log::test]
+    fn test_media_type(#[case] media_type: &str, #[case] expected: Option<Format>) {
+        assert_eq!(expected, Format::from_media_type(media_type));
+    }
+
+    #[rstest::rstest]
+    #[case("application/json", Ok(Output::Json))]
+    #[case("text/csv, application/json", Ok(Output::Csv))]
+    #[case("text/csv;q=0.5, application/yaml;q=0.9", Ok(Output::Yaml))]
+    #[case("application/toml;q=0, text/plain", Ok(Output::Text))]
+    #[case("text/plain, application/json;q=0.1", Ok(Output::Text))]
+    #[case("application/xml, application/json;q=0.1", Ok(Output::Json))]
+    #[case("*/*", Ok(Output::Text))]
+    #[case("text/*;q=0.5, */*;q=0.1", Ok(Output::Text))]
+    #[case("application/xml", Err(StatusCode::NOT_ACCEPTABLE))]
+    #[case("application/json;q=0", Err(StatusCode::NOT_ACCEPTABLE))]
+    #[test_log::test]
+    fn test_negotiate(#[case] accept: &str, #[case] expected: Result<Output, StatusCode>) {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, accept.parse().unwrap());
+
+        assert_eq!(expected, Output::negotiate(&headers, &Output::ALL));
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_negotiate_default() {
+        let supported = [Output::Json, Output::Text];
+
+        assert_eq!(
+            Ok(Output::Json),
+            Output::negotiate(&HeaderMap::new(), &supported)
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, "text/csv".parse().unwrap());
+        assert_eq!(
+            Err(StatusCode::NOT_ACCEPTABLE),
+            Output::negotiate(&headers, &supported)
+        );
+    }
+
+    #[rstest::rstest]
+    #[case("Cargo.toml", Some(Format::Toml))]
+    #[case("manifest.YML", Some(Format::Yaml))]
+    #[case("order.ron", Some(Format::Ron))]
+    #[case("Cargo", None)]
+    #[test_log::test]
+    fn test_file_name(#[case] name: &str, #[case] expected: Option<Format>) {
+        assert_eq!(expected, Format::from_file_name(name));
+    }
+}
@@ -0,0 +1,209 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{quantity::Unit, Format, GiftOrderState, LineStatus, OrderLine, Quantity};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub(super) struct GiftOrder {
+    id: Uuid,
+    package_name: String,
+    authors: Vec<String>,
+    format: String,
+    created_at: DateTime<Utc>,
+    items: Vec<OrderLine>,
+}
+
+struct GiftOrderRow {
+    id: Uuid,
+    package_name: String,
+    authors: Vec<String>,
+    format: String,
+    created_at: DateTime<Utc>,
+}
+
+struct GiftOrderItemRow {
+    order_id: Uuid,
+    item: String,
+    quantity: Quantity,
+    unit: Option<String>,
+    status: Option<String>,
+    fulfilled: Option<Quantity>,
+}
+
+pub(super) async fn save(
+    connection: &mut PgConnection,
+    package_name: &str,
+    authors: &[String],
+    format: Format,
+    lines: &[OrderLine],
+) -> Result<Uuid, sqlx::Error> {
+    let id = sqlx::query_scalar!(
+        r#"
+    INSERT INTO gift_orders (package_name, authors, format)
+        VALUES ($1, $2, $3)
+        RETURNING id;
+    "#,
+        package_name,
+        authors,
+        format.as_str()
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+
+    for (position, line) in lines.iter().enumerate() {
+        sqlx::query!(
+            r#"
+    INSERT INTO gift_order_items (order_id, position, item, quantity, unit, status, fulfilled)
+        VALUES ($1, $2, $3, $4, $5, $6, $7);
+    "#,
+            id,
+            position as i32,
+            line.item,
+            line.quantity.0,
+            line.unit.map(|unit| unit.as_str()),
+            line.status.map(|status| status.as_str()),
+            line.fulfilled.map(|fulfilled| fulfilled.0)
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    info!(?id, "Saved order");
+
+    Ok(id)
+}
+
+async fn with_items(
+    pool: &sqlx::PgPool,
+    rows: Vec<GiftOrderRow>,
+) -> Result<Vec<GiftOrder>, sqlx::Error> {
+    let ids = rows.iter().map(|row| row.id).collect::<Vec<_>>();
+    let mut items = sqlx::query_as!(
+        GiftOrderItemRow,
+        r#"SELECT order_id, item, quantity as "quantity: Quantity", unit, status, fulfilled as "fulfilled: Quantity" FROM gift_order_items WHERE order_id = ANY($1) ORDER BY order_id, position"#,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .into_group_map_by(|item| item.order_id);
+
+    Ok(rows
+        .into_iter()
+        .map(|row| GiftOrder {
+            items: items
+                .remove(&row.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|item| OrderLine {
+                    item: item.item,
+                    quantity: item.quantity,
+                    unit: item.unit.as_deref().and_then(Unit::from_str),
+                    status: item.status.as_deref().and_then(LineStatus::from_str),
+                    fulfilled: item.fulfilled,
+                })
+                .collect(),
+            id: row.id,
+            package_name: row.package_name,
+            authors: row.authors,
+            format: row.format,
+            created_at: row.created_at,
+        })
+        .collect())
+}
+
+pub(super) async fn order(Path(id): Path<Uuid>, State(state): State<GiftOrderState>) -> Response {
+    info!("order - id={}", id);
+    let row = sqlx::query_as!(
+        GiftOrderRow,
+        r#"SELECT id, package_name, authors, format, created_at FROM gift_orders WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await;
+
+    match row {
+        Ok(Some(row)) => match with_items(&state.pool, vec![row]).await {
+            Ok(mut orders) => Json(orders.remove(0)).into_response(),
+            Err(err) => {
+                warn!(?err);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            warn!(?err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Most orders returned at once
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize, Debug)]
+pub(super) struct OrderSearch {
+    author: String,
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_limit() -> i64 {
+    MAX_LIMIT
+}
+
+/// A page of the orders of `author`, oldest first
+pub(super) async fn orders(
+    Query(search): Query<OrderSearch>,
+    State(state): State<GiftOrderState>,
+) -> Response {
+    info!("orders - {:?}", search);
+    if !(1..=MAX_LIMIT).contains(&search.limit) || search.offset < 0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("The limit must be 1 to {MAX_LIMIT} and the offset not negative"),
+        )
+            .into_response();
+    }
+
+    // `@>` rather than `= ANY` so the GIN index on `authors` is used
+    let rows = sqlx::query_as!(
+        GiftOrderRow,
+        r#"
+    SELECT id, package_name, authors, format, created_at FROM gift_orders
+        WHERE authors @> ARRAY[$1]
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+    "#,
+        search.author,
+        search.limit,
+        search.offset
+    )
+    .fetch_all(&state.pool)
+    .await;
+
+    let orders = match rows {
+        Ok(rows) => with_items(&state.pool, rows).await,
+        Err(err) => Err(err),
+    };
+
+    match orders {
+        Ok(orders) => Json(orders).into_response(),
+        Err(err) => {
+            warn!(?err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+
+use cargo_manifest::{MaybeInherited, Package};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+/// A manifest policy rule, only enforced between `from` and `until` (inclusive) when given
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    /// Every keyword has to be present
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Every category has to be present
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// At least one of the authors has to be present
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+impl Rule {
+    fn is_active(&self, today: NaiveDate) -> bool {
+        self.from.is_none_or(|from| from <= today) && self.until.is_none_or(|until| today <= until)
+    }
+
+    fn check(&self, package: Option<&Package>) -> Result<(), Violation> {
+        let keywords = local(package.and_then(|package| package.keywords.clone()));
+        if let Some(keyword) = self.keywords.iter().find(|k| !keywords.contains(k)) {
+            return Err(Violation::Keyword(keyword.clone()));
+        }
+
+        let categories = local(package.and_then(|package| package.categories.clone()));
+        if let Some(category) = self.categories.iter().find(|c| !categories.contains(c)) {
+            return Err(Violation::Category(category.clone()));
+        }
+
+        let authors = local(package.and_then(|package| package.authors.clone()));
+        if !self.authors.is_empty() && !self.authors.iter().any(|a| authors.contains(a)) {
+            return Err(Violation::Author(self.authors.clone()));
+        }
+
+        if let Some(license) = &self.license {
+            let found = package
+                .and_then(|package| package.license.clone())
+                .and_then(MaybeInherited::as_local);
+            if found.as_ref() != Some(license) {
+                return Err(Violation::License(license.clone()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn local(value: Option<MaybeInherited<Vec<String>>>) -> Vec<String> {
+    value.and_then(MaybeInherited::as_local).unwrap_or_default()
+}
+
+pub(super) fn default_rules() -> Vec<Rule> {
+    vec![Rule {
+        name: "christmas-2024".to_string(),
+        keywords: vec!["Christmas 2024".to_string()],
+        categories: vec![],
+        authors: vec![],
+        license: None,
+        from: None,
+        until: None,
+    }]
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum Violation {
+    Keyword(String),
+    Category(String),
+    Author(Vec<String>),
+    License(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Keyword(keyword) => write!(f, "missing keyword `{keyword}`"),
+            Violation::Category(category) => write!(f, "missing category `{category}`"),
+            Violation::Author(authors) => write!(f, "missing one of authors {authors:?}"),
+            Violation::License(license) => write!(f, "license must be `{license}`"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum PolicyError {
+    /// Rules are configured but none of them apply today
+    OutOfSeason(NaiveDate),
+    Failed {
+        rule: String,
+        violation: Violation,
+    },
+}
+
+impl PolicyError {
+    /// Body of the 400 response, the first line stays the classic message for keyword failures
+    pub(super) fn to_text(&self) -> String {
+        match self {
+            PolicyError::OutOfSeason(today) => {
+                format!("Magic keyword not provided\nNo policy rule is active on {today}")
+            }
+            PolicyError::Failed { rule, violation } => {
+                let headline = match violation {
+                    Violation::Keyword(_) => "Magic keyword not provided",
+                    _ => "Manifest policy not satisfied",
+                };
+                format!("{headline}\nRule `{rule}` failed: {violation}")
+            }
+        }
+    }
+}
+
+/// Check every rule active on `today`, failing on the first broken one
+pub(super) fn check(
+    rules: &[Rule],
+    package: Option<&Package>,
+    today: NaiveDate,
+) -> Result<(), PolicyError> {
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let mut active = rules.iter().filter(|rule| rule.is_active(today)).peekable();
+    if active.peek().is_none() {
+        return Err(PolicyError::OutOfSeason(today));
+    }
+
+    active.try_for_each(|rule| {
+        rule.check(package)
+            .map_err(|violation| PolicyError::Failed {
+                rule: rule.name.clone(),
+                violation,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cargo_manifest::Manifest;
+
+    use super::*;
+
+    fn rules() -> Vec<Rule> {
+        toml::from_str::<Config>(
+            r#"
+[[policy]]
+name = "advent"
+keywords = ["Christmas 2024"]
+from = "2024-12-01"
+until = "2024-12-25"
+
+[[policy]]
+name = "open-source"
+categories = ["gifts"]
+authors = ["Santa", "Mrs. Claus"]
+license = "MIT"
+"#,
+        )
+        .unwrap()
+        .policy
+    }
+
+    #[derive(Deserialize)]
+    struct Config {
+        policy: Vec<Rule>,
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    const MANIFEST: &str = r#"
+[package]
+name = "gift"
+authors = ["Santa"]
+keywords = ["Christmas 2024"]
+categories = ["gifts"]
+license = "MIT"
+"#;
+
+    #[rstest::rstest]
+    #[case::valid(MANIFEST, "2024-12-10", Ok(()))]
+    #[case::after_advent("[package]\nname = \"gift\"\nauthors = [\"Santa\"]\ncategories = [\"gifts\"]\nlicense = \"MIT\"\n", "2025-01-10", Ok(()))]
+    #[case::missing_keyword(
+        "[package]\nname = \"gift\"\n",
+        "2024-12-01",
+        Err(PolicyError::Failed {
+            rule: "advent".to_string(),
+            violation: Violation::Keyword("Christmas 2024".to_string())
+        })
+    )]
+    #[case::wrong_license(
+        &MANIFEST.replace("MIT", "GPL-3.0"),
+        "2024-12-25",
+        Err(PolicyError::Failed {
+            rule: "open-source".to_string(),
+            violation: Violation::License("MIT".to_string())
+        })
+    )]
+    #[case::unknown_author(
+        &MANIFEST.replace("Santa", "Grinch"),
+        "2024-12-25",
+        Err(PolicyError::Failed {
+            rule: "open-source".to_string(),
+            violation: Violation::Author(vec!["Santa".to_string(), "Mrs. Claus".to_string()])
+        })
+    )]
+    #[test_log::test]
+    fn test_check(
+        #[case] manifest: &str,
+        #[case] today: &str,
+        #[case] expected: Result<(), PolicyError>,
+    ) {
+        let manifest = Manifest::from_str(manifest).unwrap();
+
+        assert_eq!(
+            expected,
+            check(&rules(), manifest.package.as_ref(), date(today))
+        );
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_out_of_season() {
+        let manifest = Manifest::from_str(MANIFEST).unwrap();
+        let rules = &rules()[..1];
+
+        assert_eq!(
+            Err(PolicyError::OutOfSeason(date("2024-11-30"))),
+            check(rules, manifest.package.as_ref(), date("2024-11-30"))
+        );
+        assert_eq!(Ok(()), check(&[], None, date("2024-11-30")));
+    }
+}
@@ -0,0 +1,244 @@
+use std::{fmt, str::FromStr};
+
+use rust_decimal::Decimal;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A non-negative decimal quantity, serialized as an integer whenever it is whole
+#[derive(sqlx::Type, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[sqlx(transparent)]
+pub struct Quantity(pub(super) Decimal);
+
+impl Quantity {
+    pub(super) const ZERO: Quantity = Quantity(Decimal::ZERO);
+
+    pub(super) fn checked_add(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_add(other.0).map(Quantity)
+    }
+
+    pub(super) fn checked_sub(self, other: Quantity) -> Option<Quantity> {
+        self.0.checked_sub(other.0).map(Quantity)
+    }
+
+    pub(super) fn is_whole(&self) -> bool {
+        self.0.fract().is_zero()
+    }
+
+    /// Convert into `unit`'s normalised unit
+    fn normalise(self, unit: Unit) -> Option<Quantity> {
+        self.0.checked_mul(unit.factor()).map(Quantity)
+    }
+}
+
+impl From<u32> for Quantity {
+    fn from(value: u32) -> Self {
+        Quantity(value.into())
+    }
+}
+
+impl TryFrom<Decimal> for Quantity {
+    type Error = &'static str;
+
+    fn try_from(value: Decimal) -> Result<Self, Self::Error> {
+        if value.is_sign_negative() && !value.is_zero() {
+            return Err("quantity must not be negative");
+        }
+
+        Ok(Quantity(value.normalize()))
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.normalize().fmt(f)
+    }
+}
+
+impl Serialize for Quantity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use rust_decimal::prelude::ToPrimitive;
+
+        let value = self.0.normalize();
+        match (value.is_integer(), value.to_u64(), value.to_f64()) {
+            (true, Some(value), _) => serializer.serialize_u64(value),
+            (_, _, Some(value)) => serializer.serialize_f64(value),
+            _ => serializer.serialize_str(&value.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = Quantity;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a non-negative number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Quantity, E> {
+                Ok(Quantity(value.into()))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Quantity, E> {
+                Quantity::try_from(Decimal::from(value)).map_err(E::custom)
+            }
+
+            /// Parse the shortest representation of `value`, which is the number as written,
+            /// converting the binary float itself would keep its rounding error
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Quantity, E> {
+                if !value.is_finite() {
+                    return Err(E::custom("quantity must be a finite number"));
+                }
+                let value = Decimal::from_str(&value.to_string()).map_err(E::custom)?;
+                Quantity::try_from(value).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// Units an order can be given in, each converted to the normalised unit of its dimension
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(super) enum Unit {
+    Pieces,
+    Liters,
+    Milliliters,
+    Kilograms,
+    Grams,
+}
+
+impl Unit {
+    pub(super) fn from_str(unit: &str) -> Option<Unit> {
+        match unit.to_lowercase().as_str() {
+            "pcs" | "piece" | "pieces" | "each" => Some(Unit::Pieces),
+            "l" | "liter" | "liters" | "litre" | "litres" => Some(Unit::Liters),
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => {
+                Some(Unit::Milliliters)
+            }
+            "kg" | "kilogram" | "kilograms" => Some(Unit::Kilograms),
+            "g" | "gram" | "grams" => Some(Unit::Grams),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Pieces => "pieces",
+            Unit::Liters => "liters",
+            Unit::Milliliters => "milliliters",
+            Unit::Kilograms => "kilograms",
+            Unit::Grams => "grams",
+        }
+    }
+
+    pub(super) fn normalised(&self) -> Unit {
+        match self {
+            Unit::Pieces => Unit::Pieces,
+            Unit::Liters | Unit::Milliliters => Unit::Liters,
+            Unit::Kilograms | Unit::Grams => Unit::Kilograms,
+        }
+    }
+
+    fn factor(&self) -> Decimal {
+        match self {
+            Unit::Pieces | Unit::Liters | Unit::Kilograms => Decimal::ONE,
+            Unit::Milliliters | Unit::Grams => Decimal::new(1, 3),
+        }
+    }
+}
+
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let unit = String::deserialize(deserializer)?;
+        Unit::from_str(&unit).ok_or_else(|| de::Error::custom(format!("unknown unit `{unit}`")))
+    }
+}
+
+/// Convert an order into its normalised unit, pieces have to be whole
+pub(super) fn normalise(quantity: Quantity, unit: Unit) -> Result<(Quantity, Unit), String> {
+    if unit == Unit::Pieces && !quantity.is_whole() {
+        return Err(format!("{quantity} is not a whole number of pieces"));
+    }
+
+    let normalised = quantity
+        .normalise(unit)
+        .ok_or_else(|| format!("{quantity} {} is too large", unit.as_str()))?;
+
+    Ok((normalised, unit.normalised()))
+}
+
+/// Operator limits on the total quantity of an item, in its normalised unit
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Limit {
+    pub min: Option<Quantity>,
+    pub max: Option<Quantity>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(Quantity::from(2), Unit::Pieces, Ok((Quantity::from(2), Unit::Pieces)))]
+    #[case(
+        Quantity::try_from(Decimal::new(2500, 0)).unwrap(),
+        Unit::Milliliters,
+        Ok((Quantity::try_from(Decimal::new(25, 1)).unwrap(), Unit::Liters))
+    )]
+    #[case(
+        Quantity::try_from(Decimal::new(15, 1)).unwrap(),
+        Unit::Pieces,
+        Err("1.5 is not a whole number of pieces".to_string())
+    )]
+    #[test_log::test]
+    fn test_normalise(
+        #[case] quantity: Quantity,
+        #[case] unit: Unit,
+        #[case] expected: Result<(Quantity, Unit), String>,
+    ) {
+        assert_eq!(expected, normalise(quantity, unit));
+    }
+
+    #[rstest::rstest]
+    #[case("2", Some("2"))]
+    #[case("2.50", Some("2.5"))]
+    #[case("-1", None)]
+    #[case("\"2\"", None)]
+    #[case("0.1", Some("0.1"))]
+    #[case("1e-7", Some("1e-7"))]
+    #[case("33.333333333333336", Some("33.333333333333336"))]
+    #[test_log::test]
+    fn test_serde(#[case] json: &str, #[case] expected: Option<&str>) {
+        let quantity = serde_json::from_str::<Quantity>(json).ok();
+
+        assert_eq!(
+            expected,
+            quantity
+                .map(|quantity| serde_json::to_string(&quantity).unwrap())
+                .as_deref()
+        );
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_no_float_error() {
+        let quantities = toml::from_str::<BTreeMap<String, Quantity>>("a = 0.1\nb = 0.2").unwrap();
+
+        let sum = quantities["a"].checked_add(quantities["b"]).unwrap();
+
+        assert_eq!(Decimal::new(1, 1), quantities["a"].0);
+        assert_eq!(Decimal::new(3, 1), sum.0);
+        assert_eq!("0.3", sum.to_string());
+    }
+}
@@ -0,0 +1,499 @@
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+    Json,
+};
+use cargo_manifest::Manifest;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::{debug, info};
+
+use super::format::{read_manifest, ron_options, Format};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ProblemKind {
+    Syntax,
+    InvalidType,
+    UnknownKey,
+    InvalidVersion,
+    InvalidRustVersion,
+    InvalidDependency,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+struct Problem {
+    kind: ProblemKind,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pointer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+}
+
+impl Problem {
+    fn at(kind: ProblemKind, pointer: &str, message: impl Into<String>) -> Self {
+        Problem {
+            kind,
+            message: message.into(),
+            pointer: Some(pointer.to_string()),
+            line: None,
+            column: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Report {
+    valid: bool,
+    format: Format,
+    problems: Vec<Problem>,
+}
+
+/// Escape a single JSON pointer token (RFC 6901)
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn ignored_pointer(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => {
+            format!("{}/{index}", ignored_pointer(parent))
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            format!("{}/{}", ignored_pointer(parent), escape(key))
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => ignored_pointer(parent),
+    }
+}
+
+fn error_pointer(path: &serde_path_to_error::Path) -> String {
+    path.iter()
+        .filter_map(|segment| match segment {
+            serde_path_to_error::Segment::Seq { index } => Some(format!("/{index}")),
+            serde_path_to_error::Segment::Map { key } => Some(format!("/{}", escape(key))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 1 based line and column of a byte offset
+fn line_column(data: &str, offset: usize) -> (usize, usize) {
+    let before = &data[..offset.min(data.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+
+    (line, column)
+}
+
+fn parse(format: Format, data: &str) -> Result<Value, Problem> {
+    let syntax = |message: String, position: Option<(usize, usize)>| Problem {
+        kind: ProblemKind::Syntax,
+        message,
+        pointer: None,
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+    };
+
+    match format {
+        Format::Toml => toml::from_str::<toml::Table>(data)
+            .map_err(|err| {
+                let position = err.span().map(|span| line_column(data, span.start));
+                let message = match err.message() {
+                    "" => "Invalid TOML".to_string(),
+                    message => message.replace('\n', ", "),
+                };
+                syntax(message, position)
+            })
+            .and_then(|table| {
+                serde_json::to_value(table).map_err(|err| syntax(err.to_string(), None))
+            }),
+        Format::Json => serde_json::from_str(data)
+            .map_err(|err| syntax(err.to_string(), Some((err.line(), err.column())))),
+        Format::Yaml => serde_yaml::from_str(data).map_err(|err| {
+            let position = err
+                .location()
+                .map(|location| (location.line(), location.column()));
+            syntax(err.to_string(), position)
+        }),
+        Format::Json5 => json5::from_str(data).map_err(|err| {
+            let position = err
+                .position()
+                .map(|position| (position.line + 1, position.column + 1));
+            syntax(err.to_string(), position)
+        }),
+        Format::Ron => ron_options().from_str(data).map_err(|err| {
+            let position = (err.span.start.line, err.span.start.col);
+            syntax(err.code.to_string(), Some(position))
+        }),
+    }
+}
+
+/// Problems found while deserializing into a cargo manifest, the first type error and every unknown key
+fn check_types(format: Format, data: &str, value: &Value) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut unknown = |path: serde_ignored::Path| {
+        let pointer = ignored_pointer(&path);
+        let key = pointer.rsplit('/').next().unwrap_or_default();
+        problems.push(Problem::at(
+            ProblemKind::UnknownKey,
+            &pointer,
+            format!("Unknown key `{key}`"),
+        ));
+    };
+
+    let result: Result<Manifest, _> =
+        serde_path_to_error::deserialize(serde_ignored::Deserializer::new(value, &mut unknown));
+
+    if let Err(err) = result {
+        let mut problem = Problem::at(
+            ProblemKind::InvalidType,
+            &error_pointer(err.path()),
+            err.inner().to_string(),
+        );
+
+        if format == Format::Toml {
+            let position = toml::from_str::<Manifest>(data)
+                .err()
+                .and_then(|err| err.span())
+                .map(|span| line_column(data, span.start));
+            problem.line = position.map(|(line, _)| line);
+            problem.column = position.map(|(_, column)| column);
+        }
+
+        problems.push(problem);
+    }
+
+    problems
+}
+
+fn is_inherited(value: &Value) -> bool {
+    value.get("workspace") == Some(&Value::Bool(true))
+}
+
+fn check_package(package: &Map<String, Value>, problems: &mut Vec<Problem>) {
+    if let Some(Value::String(version)) = package.get("version") {
+        if let Err(err) = Version::parse(version) {
+            problems.push(Problem::at(
+                ProblemKind::InvalidVersion,
+                "/package/version",
+                format!("`{version}` is not a valid semver version: {err}"),
+            ));
+        }
+    }
+
+    if let Some(Value::String(rust_version)) = package.get("rust-version") {
+        let parts = rust_version.split('.').collect::<Vec<_>>();
+        let valid = (2..=3).contains(&parts.len())
+            && parts
+                .iter()
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+
+        if !valid {
+            problems.push(Problem::at(
+                ProblemKind::InvalidRustVersion,
+                "/package/rust-version",
+                format!(
+                    "`{rust_version}` must be a bare `major.minor` or `major.minor.patch` version"
+                ),
+            ));
+        }
+    }
+}
+
+const DEPENDENCY_KEYS: &[&str] = &[
+    "version",
+    "registry",
+    "registry-index",
+    "path",
+    "git",
+    "branch",
+    "tag",
+    "rev",
+    "features",
+    "optional",
+    "default-features",
+    "default_features",
+    "package",
+    "public",
+    "artifact",
+    "lib",
+    "target",
+];
+
+const INHERITED_DEPENDENCY_KEYS: &[&str] =
+    &["workspace", "features", "optional", "default-features"];
+
+fn check_dependency(pointer: &str, dependency: &Value, problems: &mut Vec<Problem>) {
+    let mut invalid = |message: String| {
+        problems.push(Problem::at(
+            ProblemKind::InvalidDependency,
+            pointer,
+            message,
+        ));
+    };
+
+    let detail = match dependency {
+        Value::String(req) => {
+            if let Err(err) = VersionReq::parse(req) {
+                invalid(format!("`{req}` is not a valid version requirement: {err}"));
+            }
+            return;
+        }
+        Value::Object(detail) => detail,
+        _ => {
+            invalid("Dependency must be a version string or a table".to_string());
+            return;
+        }
+    };
+
+    if let Some(workspace) = detail.get("workspace") {
+        if workspace != &Value::Bool(true) {
+            invalid("`workspace` can only be `true`".to_string());
+        }
+        for key in detail.keys() {
+            if !INHERITED_DEPENDENCY_KEYS.contains(&key.as_str()) {
+                invalid(format!("`{key}` cannot be set on an inherited dependency"));
+            }
+        }
+    } else {
+        for key in detail.keys() {
+            if !DEPENDENCY_KEYS.contains(&key.as_str()) {
+                invalid(format!("Unknown dependency key `{key}`"));
+            }
+        }
+
+        if !["version", "path", "git"]
+            .iter()
+            .any(|key| detail.contains_key(*key))
+        {
+            invalid("Dependency must specify one of `version`, `path` or `git`".to_string());
+        }
+
+        let references = ["branch", "tag", "rev"]
+            .iter()
+            .filter(|key| detail.contains_key(**key))
+            .collect::<Vec<_>>();
+        if !references.is_empty() && !detail.contains_key("git") {
+            invalid("`branch`, `tag` and `rev` require `git`".to_string());
+        }
+        if references.len() > 1 {
+            invalid("Only one of `branch`, `tag` or `rev` may be specified".to_string());
+        }
+    }
+
+    match detail.get("version") {
+        Some(Value::String(req)) => {
+            if let Err(err) = VersionReq::parse(req) {
+                invalid(format!("`{req}` is not a valid version requirement: {err}"));
+            }
+        }
+        Some(_) => invalid("`version` must be a string".to_string()),
+        None => {}
+    }
+
+    if let Some(features) = detail.get("features") {
+        let valid = features
+            .as_array()
+            .is_some_and(|features| features.iter().all(Value::is_string));
+        if !valid {
+            invalid("`features` must be an array of strings".to_string());
+        }
+    }
+}
+
+fn check_dependency_tables(pointer: &str, table: &Map<String, Value>, problems: &mut Vec<Problem>) {
+    for key in [
+        "dependencies",
+        "dev-dependencies",
+        "dev_dependencies",
+        "build-dependencies",
+        "build_dependencies",
+    ] {
+        if let Some(Value::Object(dependencies)) = table.get(key) {
+            for (name, dependency) in dependencies {
+                let pointer = format!("{pointer}/{key}/{}", escape(name));
+                check_dependency(&pointer, dependency, problems);
+            }
+        }
+    }
+}
+
+/// Semantic problems that deserializing alone doesn't catch
+fn check_values(value: &Value) -> Vec<Problem> {
+    let mut problems = vec![];
+    let Value::Object(root) = value else {
+        return problems;
+    };
+
+    if let Some(Value::Object(package)) = root.get("package") {
+        check_package(package, &mut problems);
+    }
+
+    check_dependency_tables("", root, &mut problems);
+
+    if let Some(Value::Object(targets)) = root.get("target") {
+        for (target, table) in targets {
+            if let Value::Object(table) = table {
+                let pointer = format!("/target/{}", escape(target));
+                check_dependency_tables(&pointer, table, &mut problems);
+            }
+        }
+    }
+
+    if let Some(Value::Object(workspace)) = root.get("workspace") {
+        if let Some(Value::Object(dependencies)) = workspace.get("dependencies") {
+            for (name, dependency) in dependencies {
+                let pointer = format!("/workspace/dependencies/{}", escape(name));
+                if is_inherited(dependency) {
+                    problems.push(Problem::at(
+                        ProblemKind::InvalidDependency,
+                        &pointer,
+                        "Workspace dependencies cannot be inherited",
+                    ));
+                } else {
+                    check_dependency(&pointer, dependency, &mut problems);
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+fn report(format: Format, data: &str) -> Report {
+    let problems = match parse(format, data) {
+        Ok(value) => {
+            debug!(?value);
+            let mut problems = check_types(format, data, &value);
+            problems.extend(check_values(&value));
+            problems
+        }
+        Err(problem) => vec![problem],
+    };
+
+    Report {
+        valid: problems.is_empty(),
+        format,
+        problems,
+    }
+}
+
+pub(super) async fn validate(request: Request) -> Response {
+    let (format, data) = match read_manifest(request).await {
+        Ok(manifest) => manifest,
+        Err(status) => return status.into_response(),
+    };
+
+    let report = report(format, &data);
+    info!(?report);
+
+    Json(report).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header::CONTENT_TYPE, StatusCode},
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case::valid(
+        Format::Toml,
+        r#"
+[package]
+name = "not-a-gift-order"
+version = "1.0.0"
+rust-version = "1.69"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+"#,
+        json!([])
+    )]
+    #[case::toml_syntax(
+        Format::Toml,
+        "[package]\nname = gift\n",
+        json!([{"kind": "syntax", "message": "invalid string, expected `\"`, `'`", "line": 2, "column": 8}])
+    )]
+    #[case::json_syntax(
+        Format::Json,
+        "{\n  \"package\": {\n    \"name\": \"gift\",\n  }\n}",
+        json!([{"kind": "syntax", "message": "trailing comma at line 4 column 3", "line": 4, "column": 3}])
+    )]
+    #[case::invalid_type(
+        Format::Toml,
+        "[package]\nname = false\n",
+        json!([{"kind": "invalid_type", "message": "invalid type: boolean `false`, expected a string", "pointer": "/package/name", "line": 2, "column": 8}])
+    )]
+    #[case::unknown_key(
+        Format::Yaml,
+        "package:\n  name: gift\n  colour: red\n",
+        json!([{"kind": "unknown_key", "message": "Unknown key `colour`", "pointer": "/package/colour"}])
+    )]
+    #[case::bad_versions(
+        Format::Json,
+        r#"{"package": {"name": "gift", "version": "1.0", "rust-version": "1.69-nightly"}}"#,
+        json!([
+            {"kind": "invalid_version", "message": "`1.0` is not a valid semver version: unexpected end of input while parsing minor version number", "pointer": "/package/version"},
+            {"kind": "invalid_rust_version", "message": "`1.69-nightly` must be a bare `major.minor` or `major.minor.patch` version", "pointer": "/package/rust-version"}
+        ])
+    )]
+    #[case::bad_dependencies(
+        Format::Toml,
+        r#"
+[package]
+name = "gift"
+
+[dependencies]
+serde = "not a version"
+local = { features = ["derive"] }
+remote = { git = "https://example.com/remote", tag = "v1", rev = "abc" }
+
+[target.'cfg(unix)'.dev-dependencies]
+unix = { path = "../unix", colour = "red" }
+"#,
+        json!([
+            {"kind": "invalid_dependency", "message": "Dependency must specify one of `version`, `path` or `git`", "pointer": "/dependencies/local"},
+            {"kind": "invalid_dependency", "message": "Only one of `branch`, `tag` or `rev` may be specified", "pointer": "/dependencies/remote"},
+            {"kind": "invalid_dependency", "message": "`not a version` is not a valid version requirement: unexpected character 'n' while parsing major version number", "pointer": "/dependencies/serde"},
+            {"kind": "invalid_dependency", "message": "Unknown dependency key `colour`", "pointer": "/target/cfg(unix)/dev-dependencies/unix"}
+        ])
+    )]
+    #[test_log::test]
+    fn test_report(#[case] format: Format, #[case] data: &str, #[case] expected: Value) {
+        let report = report(format, data);
+
+        assert_eq!(expected, serde_json::to_value(&report.problems).unwrap());
+        assert_eq!(report.valid, report.problems.is_empty());
+    }
+
+    #[rstest::rstest]
+    #[case("application/toml", StatusCode::OK)]
+    #[case("application/html", StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+    #[test_log::test(tokio::test)]
+    async fn test_validate(#[case] content_type: &str, #[case] expected: StatusCode) {
+        let request = Request::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from("[package]\nname = false\n"))
+            .unwrap();
+
+        let response = validate(request).await;
+
+        assert_eq!(expected, response.status());
+    }
+}
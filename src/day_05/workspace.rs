@@ -0,0 +1,403 @@
+use std::iter;
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use cargo_manifest::{Dependency, DependencyDetail, DepsSet, Manifest, MaybeInherited, Package};
+use serde::Serialize;
+use toml::Value;
+use tracing::{info, warn};
+
+use super::{
+    format::{read_manifests, Manifests},
+    Metadata,
+};
+
+/// A root manifest along with any member manifests submitted alongside it
+#[derive(Debug)]
+pub(super) struct Workspace {
+    root: Manifest,
+    members: Vec<Manifest>,
+}
+
+impl Workspace {
+    pub(super) fn parse(manifests: &Manifests) -> Result<Workspace, String> {
+        let root = manifests
+            .manifest
+            .format
+            .deserialize(&manifests.manifest.data)?;
+        let members = manifests
+            .members
+            .iter()
+            .map(|member| member.format.deserialize(&member.data))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Workspace { root, members })
+    }
+
+    /// Manifests with a `[package]`, with `workspace = true` fields and dependencies taken from
+    /// the root's `[workspace]`
+    pub(super) fn resolve(&self) -> Vec<Manifest> {
+        let workspace = self.root.workspace.as_ref();
+        let inherited = workspace.and_then(|workspace| workspace.package.as_ref());
+        let dependencies = workspace.and_then(|workspace| workspace.dependencies.as_ref());
+
+        iter::once(&self.root)
+            .chain(&self.members)
+            .filter(|manifest| manifest.package.is_some())
+            .cloned()
+            .map(|mut manifest| {
+                if let (Some(package), Some(inherited)) = (manifest.package.as_mut(), inherited) {
+                    inherit(&mut package.version, &inherited.version);
+                    inherit(&mut package.authors, &inherited.authors);
+                    inherit(&mut package.description, &inherited.description);
+                    inherit(&mut package.keywords, &inherited.keywords);
+                    inherit(&mut package.categories, &inherited.categories);
+                    inherit(&mut package.license, &inherited.license);
+                    inherit(&mut package.repository, &inherited.repository);
+                    inherit(&mut package.rust_version, &inherited.rust_version);
+                }
+
+                if let Some(dependencies) = dependencies {
+                    let sets = [
+                        manifest.dependencies.as_mut(),
+                        manifest.dev_dependencies.as_mut(),
+                        manifest.build_dependencies.as_mut(),
+                    ];
+                    let targets = manifest.target.iter_mut().flat_map(|targets| {
+                        targets.values_mut().flat_map(|target| {
+                            [
+                                &mut target.dependencies,
+                                &mut target.dev_dependencies,
+                                &mut target.build_dependencies,
+                            ]
+                        })
+                    });
+                    for set in sets.into_iter().flatten().chain(targets) {
+                        inherit_dependencies(set, dependencies);
+                    }
+                }
+
+                manifest
+            })
+            .collect()
+    }
+
+    /// Orders from `[workspace.metadata]` followed by those of every package
+    pub(super) fn orders(&self, resolved: &[Manifest]) -> Vec<Value> {
+        let workspace = self
+            .root
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.metadata.clone());
+        let packages = resolved
+            .iter()
+            .filter_map(|manifest| manifest.package.as_ref())
+            .map(|package| package.metadata.clone());
+
+        iter::once(workspace)
+            .chain(packages)
+            .flatten()
+            .flat_map(|metadata| {
+                let metadata: Metadata = metadata.try_into().unwrap_or_default();
+
+                metadata.orders
+            })
+            .collect()
+    }
+
+    /// Every dependency of every package, after inheritance
+    pub(super) fn dependencies(&self) -> Vec<Dep> {
+        let mut deps = vec![];
+        for manifest in self.resolve() {
+            let Some(package) = &manifest.package else {
+                continue;
+            };
+
+            let sets = [
+                (DepKind::Normal, None, &manifest.dependencies),
+                (DepKind::Dev, None, &manifest.dev_dependencies),
+                (DepKind::Build, None, &manifest.build_dependencies),
+            ];
+            let targets = manifest.target.iter().flatten().flat_map(|(target, set)| {
+                [
+                    (DepKind::Normal, Some(target), &set.dependencies),
+                    (DepKind::Dev, Some(target), &set.dev_dependencies),
+                    (DepKind::Build, Some(target), &set.build_dependencies),
+                ]
+            });
+            let sets = sets
+                .into_iter()
+                .filter_map(|(kind, target, set)| set.as_ref().map(|set| (kind, target, set)))
+                .chain(targets);
+
+            for (kind, target, set) in sets {
+                deps.extend(
+                    set.iter().map(|(name, dependency)| {
+                        Dep::new(package, kind, target, name, dependency)
+                    }),
+                );
+            }
+        }
+
+        deps
+    }
+}
+
+fn inherit<T: Clone>(field: &mut Option<MaybeInherited<T>>, workspace: &Option<T>) {
+    if let (Some(MaybeInherited::Inherited { .. }), Some(value)) = (&field, workspace) {
+        *field = Some(MaybeInherited::Local(value.clone()));
+    }
+}
+
+/// Replace `{ workspace = true }` dependencies, features are added to the workspace's and
+/// `optional` can only be set by the member
+fn inherit_dependencies(set: &mut DepsSet, workspace: &DepsSet) {
+    for (name, dependency) in set.iter_mut() {
+        let (Dependency::Inherited(member), Some(inherited)) = (&*dependency, workspace.get(name))
+        else {
+            continue;
+        };
+
+        let mut detail = match inherited {
+            Dependency::Simple(version) => DependencyDetail {
+                version: Some(version.clone()),
+                ..Default::default()
+            },
+            Dependency::Detailed(detail) => detail.clone(),
+            Dependency::Inherited(_) => continue,
+        };
+        if let Some(features) = &member.features {
+            let all = detail.features.get_or_insert_with(Vec::new);
+            for feature in features {
+                if !all.contains(feature) {
+                    all.push(feature.clone());
+                }
+            }
+        }
+        detail.optional = member.optional;
+
+        *dependency = Dependency::Detailed(detail);
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum DepKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+/// A normalized dependency of a workspace package
+#[derive(Serialize, PartialEq, Debug)]
+pub(super) struct Dep {
+    package: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rename: Option<String>,
+    kind: DepKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    req: String,
+    features: Vec<String>,
+    optional: bool,
+    source: String,
+}
+
+impl Dep {
+    fn new(
+        package: &Package,
+        kind: DepKind,
+        target: Option<&String>,
+        name: &str,
+        dependency: &Dependency,
+    ) -> Dep {
+        let rename = dependency.package().map(|_| name.to_string());
+        let req = dependency.req();
+
+        Dep {
+            package: package.name.clone(),
+            name: dependency.package().unwrap_or(name).to_string(),
+            rename,
+            kind,
+            target: target.cloned(),
+            req: semver::VersionReq::parse(req).map_or(req.to_string(), |req| req.to_string()),
+            features: dependency.req_features().to_vec(),
+            optional: dependency.optional(),
+            source: source(dependency),
+        }
+    }
+}
+
+/// Cargo style source ids, e.g. `git+https://github.com/serde-rs/serde?tag=v1.0.0`
+fn source(dependency: &Dependency) -> String {
+    let detail = match dependency {
+        Dependency::Simple(_) => return "crates-io".to_string(),
+        Dependency::Inherited(_) => return "workspace".to_string(),
+        Dependency::Detailed(detail) => detail,
+    };
+
+    if let Some(git) = &detail.git {
+        let reference = [
+            ("branch", &detail.branch),
+            ("tag", &detail.tag),
+            ("rev", &detail.rev),
+        ]
+        .into_iter()
+        .find_map(|(kind, value)| value.as_ref().map(|value| format!("?{kind}={value}")));
+        format!("git+{git}{}", reference.unwrap_or_default())
+    } else if let Some(path) = &detail.path {
+        format!("path+{path}")
+    } else if let Some(registry) = detail.registry.as_ref().or(detail.registry_index.as_ref()) {
+        format!("registry+{registry}")
+    } else {
+        "crates-io".to_string()
+    }
+}
+
+pub(super) async fn deps(request: Request) -> Response {
+    let manifests = match read_manifests(request).await {
+        Ok(manifests) => manifests,
+        Err(status) => return status.into_response(),
+    };
+
+    match Workspace::parse(&manifests) {
+        Ok(workspace) => {
+            let deps = workspace.dependencies();
+            info!(?deps);
+            Json(deps).into_response()
+        }
+        Err(err) => {
+            warn!(?err, "Bad manifest");
+            (StatusCode::BAD_REQUEST, "Invalid manifest").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::format::{Format, Upload};
+    use super::*;
+
+    const ROOT: &str = r#"
+[workspace]
+members = ["sleigh", "reindeer"]
+
+[workspace.package]
+authors = ["Santa"]
+keywords = ["Christmas 2024"]
+license = "MIT"
+
+[workspace.dependencies]
+serde = { version = "1.0", features = ["derive"] }
+tokio = "1"
+
+[workspace.metadata]
+orders = [{ item = "Coal", quantity = 1 }]
+"#;
+
+    const SLEIGH: &str = r#"
+[package]
+name = "sleigh"
+authors.workspace = true
+keywords.workspace = true
+
+[package.metadata]
+orders = [{ item = "Toy car", quantity = 2 }]
+
+[dependencies]
+serde = { workspace = true, features = ["rc"], optional = true }
+bells = { git = "https://example.com/bells", tag = "v1" }
+
+[target.'cfg(unix)'.dev-dependencies]
+tokio = { workspace = true }
+"#;
+
+    const REINDEER: &str = r#"
+[package]
+name = "reindeer"
+keywords = ["Rudolph"]
+license.workspace = true
+
+[build-dependencies]
+nose = { path = "../nose", package = "red-nose" }
+"#;
+
+    fn workspace() -> Workspace {
+        let upload = |data: &str| Upload {
+            format: Format::Toml,
+            data: data.to_string(),
+        };
+        let manifests = Manifests {
+            manifest: upload(ROOT),
+            members: vec![upload(SLEIGH), upload(REINDEER)],
+        };
+
+        Workspace::parse(&manifests).unwrap()
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_resolve() {
+        let workspace = workspace();
+        let resolved = workspace.resolve();
+        let packages = resolved
+            .iter()
+            .filter_map(|manifest| manifest.package.clone())
+            .map(|package| {
+                (
+                    package.name,
+                    package.keywords.and_then(MaybeInherited::as_local),
+                    package.license.and_then(MaybeInherited::as_local),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                (
+                    "sleigh".to_string(),
+                    Some(vec!["Christmas 2024".to_string()]),
+                    None
+                ),
+                (
+                    "reindeer".to_string(),
+                    Some(vec!["Rudolph".to_string()]),
+                    Some("MIT".to_string())
+                ),
+            ],
+            packages
+        );
+
+        let orders = workspace.orders(&resolved);
+        assert_eq!(
+            vec!["Coal", "Toy car"],
+            orders
+                .iter()
+                .filter_map(|order| order.get("item")?.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_dependencies() {
+        let deps = serde_json::to_value(workspace().dependencies()).unwrap();
+
+        assert_eq!(
+            json!([
+                {"package": "sleigh", "name": "bells", "kind": "normal", "req": "*", "features": [], "optional": false, "source": "git+https://example.com/bells?tag=v1"},
+                {"package": "sleigh", "name": "serde", "kind": "normal", "req": "^1.0", "features": ["derive", "rc"], "optional": true, "source": "crates-io"},
+                {"package": "sleigh", "name": "tokio", "kind": "dev", "target": "cfg(unix)", "req": "^1", "features": [], "optional": false, "source": "crates-io"},
+                {"package": "reindeer", "name": "red-nose", "rename": "nose", "kind": "build", "req": "*", "features": [], "optional": false, "source": "path+../nose"}
+            ]),
+            deps
+        );
+    }
+}
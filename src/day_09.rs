@@ -106,7 +106,7 @@ mod tests {
     use super::*;
 
     async fn check_milk_request(server: &TestServer, expected_status: StatusCode) {
-        let result = server.post(&"/milk").await;
+        let result = server.post("/milk").await;
 
         assert_eq!(expected_status, result.status_code());
         match expected_status {
@@ -138,7 +138,7 @@ mod tests {
     async fn test_milk_invalid_json() {
         let server = TestServer::new(router()).unwrap();
         let result = server
-            .post(&"/milk")
+            .post("/milk")
             .json(&serde_json::from_str::<Value>(r#"{"liters":1,"gallons":5}"#).unwrap())
             .await;
 
@@ -152,7 +152,7 @@ mod tests {
         expected_status: StatusCode,
         expected_unit: MilkUnit,
     ) {
-        let result = server.post(&"/milk").json(&MilkConversion { unit }).await;
+        let result = server.post("/milk").json(&MilkConversion { unit }).await;
 
         debug!(?result);
         assert_eq!(expected_status, result.status_code());
@@ -224,7 +224,7 @@ mod tests {
             check_milk_request(&server, StatusCode::OK).await;
         }
         check_milk_request(&server, StatusCode::TOO_MANY_REQUESTS).await;
-        let result = server.post(&"/refill").await;
+        let result = server.post("/refill").await;
         assert_eq!(StatusCode::OK, result.status_code());
         for _ in 0..5 {
             check_milk_request(&server, StatusCode::OK).await;
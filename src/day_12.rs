@@ -278,7 +278,7 @@ mod tests {
     #[rstest::rstest]
     #[test_log::test(tokio::test)]
     async fn test_initial_board(server: TestServer) {
-        let result = server.get(&"/board").await;
+        let result = server.get("/board").await;
 
         debug!(?result);
         result.assert_status_ok();
@@ -288,7 +288,7 @@ mod tests {
     #[rstest::rstest]
     #[test_log::test(tokio::test)]
     async fn test_reset(server: TestServer) {
-        let result = server.post(&"/reset").await;
+        let result = server.post("/reset").await;
 
         debug!(?result);
         result.assert_status_ok();
@@ -298,7 +298,7 @@ mod tests {
     #[rstest::rstest]
     #[test_log::test(tokio::test)]
     async fn test_invalid_column(server: TestServer) {
-        let result = server.post(&"/place/cookie/fun").await;
+        let result = server.post("/place/cookie/fun").await;
 
         debug!(?result);
         result.assert_status_bad_request();
@@ -455,7 +455,7 @@ No winner.
     #[rstest::rstest]
     #[test_log::test(tokio::test)]
     async fn test_random(server: TestServer) {
-        let result = server.get(&"/random-board").await;
+        let result = server.get("/random-board").await;
 
         debug!(?result);
         result.assert_status_ok();
@@ -469,7 +469,7 @@ No winner.
 ",
         );
 
-        let result = server.get(&"/random-board").await;
+        let result = server.get("/random-board").await;
 
         debug!(?result);
         result.assert_status_ok();
@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{errors::ErrorKind, Validation};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Claims added to wrapped gifts, removed again when unwrapping
+const REGISTERED: [&str; 7] = ["iat", "exp", "nbf", "iss", "aud", "jti", "sub"];
+
+/// Required claims jsonwebtoken doesn't check the presence of, see `Claims::require`
+const UNCHECKED: [&str; 2] = ["iat", "jti"];
+
+/// Claims checked by `crate::auth`, which can't be picked by whoever wraps a gift
+const RESERVED: [&str; 3] = ["role", "roles", "sub"];
+
+/// Claim holding a gift that isn't a JSON object, so any JSON value can be wrapped
+const WRAPPED: &str = "wrapped";
+
+/// Registered claims policy, the `[claims]` table of the day 16 config
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub(super) struct Claims {
+    pub(super) issuer: Option<String>,
+    pub(super) audience: Option<String>,
+    /// Lifetime of new tokens in seconds
+    pub(super) ttl: u64,
+    /// Longest lifetime `/16/wrap?ttl=` may ask for
+    pub(super) max_ttl: u64,
+    /// Lifetime of a refresh token family in seconds, rotating doesn't extend it
+    pub(super) refresh_ttl: u64,
+    /// Allowed clock skew in seconds
+    pub(super) leeway: u64,
+}
+
+impl Default for Claims {
+    fn default() -> Self {
+        Claims {
+            issuer: None,
+            audience: None,
+            ttl: 3600,
+            max_ttl: 86400,
+            refresh_ttl: 30 * 86400,
+            leeway: 60,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub(super) enum ClaimsError {
+    TtlTooLong(u64),
+    Reserved(&'static str),
+}
+
+impl ClaimsError {
+    pub(super) fn to_text(&self) -> String {
+        match self {
+            ClaimsError::TtlTooLong(max) => format!("The ttl can be at most {max} seconds"),
+            ClaimsError::Reserved(claim) => format!("The `{claim}` claim can't be wrapped"),
+        }
+    }
+}
+
+impl Claims {
+    /// Add the registered claims to `payload` for `subject`, refusing any sent by the caller
+    pub(super) fn issue(
+        &self,
+        payload: Value,
+        subject: &str,
+        ttl: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Result<Value, ClaimsError> {
+        let mut claims = match payload {
+            Value::Object(claims) => {
+                if let Some(claim) = RESERVED
+                    .into_iter()
+                    .chain(REGISTERED)
+                    .chain([WRAPPED])
+                    .find(|claim| claims.contains_key(*claim))
+                {
+                    return Err(ClaimsError::Reserved(claim));
+                }
+                claims
+            }
+            gift => Map::from_iter([(WRAPPED.to_string(), gift)]),
+        };
+        let ttl = ttl.unwrap_or(self.ttl);
+        if ttl > self.max_ttl {
+            return Err(ClaimsError::TtlTooLong(self.max_ttl));
+        }
+
+        let now = now.timestamp();
+        claims.insert("sub".to_string(), subject.into());
+        claims.insert("iat".to_string(), now.into());
+        claims.insert("nbf".to_string(), now.into());
+        claims.insert("exp".to_string(), (now + ttl as i64).into());
+        claims.insert("jti".to_string(), Uuid::new_v4().to_string().into());
+        if let Some(issuer) = &self.issuer {
+            claims.insert("iss".to_string(), issuer.clone().into());
+        }
+        if let Some(audience) = &self.audience {
+            claims.insert("aud".to_string(), audience.clone().into());
+        }
+
+        Ok(Value::Object(claims))
+    }
+
+    /// Require and check the claims `issue` adds
+    pub(super) fn configure(&self, validation: &mut Validation) {
+        let mut required = vec!["exp", "nbf", "iat", "jti"];
+        validation.leeway = self.leeway;
+        validation.validate_exp = true;
+        validation.validate_nbf = true;
+        validation.validate_aud = self.audience.is_some();
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+            required.push("iss");
+        }
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+            required.push("aud");
+        }
+        validation.set_required_spec_claims(&required);
+    }
+
+    /// Refuse verified `claims` without the `UNCHECKED` claims `configure` requires
+    pub(super) fn require(claims: &Value) -> Result<(), jsonwebtoken::errors::Error> {
+        let Value::Object(claims) = claims else {
+            return Err(ErrorKind::InvalidToken.into());
+        };
+        match UNCHECKED
+            .into_iter()
+            .find(|claim| !claims.contains_key(*claim))
+        {
+            Some(claim) => Err(ErrorKind::MissingRequiredClaim(claim.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+
+    /// The gift wrapped in verified `claims`
+    pub(super) fn open(claims: Value) -> Result<Value, jsonwebtoken::errors::Error> {
+        let Value::Object(claims) = claims else {
+            return Err(ErrorKind::InvalidToken.into());
+        };
+
+        let mut payload = claims
+            .into_iter()
+            .filter(|(claim, _)| !REGISTERED.contains(&claim.as_str()))
+            .collect::<Map<_, _>>();
+
+        Ok(payload.remove(WRAPPED).unwrap_or(Value::Object(payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(json!({"gift": true}), None, Ok(3600))]
+    #[case(json!({"gift": true}), Some(60), Ok(60))]
+    #[case(json!({"gift": true, "exp": 0}), None, Err(ClaimsError::Reserved("exp")))]
+    #[case(json!({"gift": true}), Some(86401), Err(ClaimsError::TtlTooLong(86400)))]
+    #[case(json!(["gift"]), None, Ok(3600))]
+    #[case(json!("gift"), None, Ok(3600))]
+    #[case(json!({"gift": {"nested": true}}), None, Ok(3600))]
+    #[case(json!({"gift": true, "wrapped": 1}), None, Err(ClaimsError::Reserved("wrapped")))]
+    #[case(json!({"gift": true, "role": "elf"}), None, Err(ClaimsError::Reserved("role")))]
+    #[case(json!({"gift": true, "sub": "elf"}), None, Err(ClaimsError::Reserved("sub")))]
+    #[case(json!({"gift": true, "aud": "elves"}), None, Err(ClaimsError::Reserved("aud")))]
+    #[test_log::test]
+    fn test_issue(
+        #[case] payload: Value,
+        #[case] ttl: Option<u64>,
+        #[case] expected: Result<i64, ClaimsError>,
+    ) {
+        let claims = Claims {
+            issuer: Some("north-pole".to_string()),
+            ..Default::default()
+        };
+        let now = DateTime::from_timestamp(1_734_000_000, 0).unwrap();
+
+        let issued = match claims.issue(payload.clone(), "rudolph", ttl, now) {
+            Ok(issued) => issued,
+            Err(err) => return assert_eq!(expected, Err(err)),
+        };
+
+        assert_eq!(
+            expected,
+            Ok(issued["exp"].as_i64().unwrap() - issued["iat"].as_i64().unwrap())
+        );
+        assert_eq!("north-pole", issued["iss"]);
+        assert_eq!("rudolph", issued["sub"]);
+        assert!(issued.get("aud").is_none());
+        assert!(Claims::require(&issued).is_ok());
+        assert_eq!(payload, Claims::open(issued).unwrap());
+    }
+
+    #[rstest::rstest]
+    #[case::complete(json!({"iat": 0, "jti": "a"}), None)]
+    #[case::missing_iat(json!({"jti": "a"}), Some("iat"))]
+    #[case::missing_jti(json!({"iat": 0}), Some("jti"))]
+    #[test_log::test]
+    fn test_require(#[case] claims: Value, #[case] missing: Option<&str>) {
+        let result = Claims::require(&claims).map_err(|err| err.into_kind());
+
+        assert_eq!(
+            missing.map(|claim| ErrorKind::MissingRequiredClaim(claim.to_string())),
+            result.err()
+        );
+    }
+}
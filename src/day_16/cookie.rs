@@ -0,0 +1,184 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::{
+    cookie::{Cookie, Key, SameSite},
+    CookieJar, PrivateCookieJar, SignedCookieJar,
+};
+use serde::Deserialize;
+use time::Duration;
+
+/// How the gift cookie is protected on top of the token's own signature
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Mode {
+    #[default]
+    Plain,
+    /// Tamper proof, but readable by the client
+    Signed,
+    /// Encrypted, the client can't read the token
+    Private,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum SameSiteConfig {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<SameSiteConfig> for SameSite {
+    fn from(same_site: SameSiteConfig) -> Self {
+        match same_site {
+            SameSiteConfig::Strict => SameSite::Strict,
+            SameSiteConfig::Lax => SameSite::Lax,
+            SameSiteConfig::None => SameSite::None,
+        }
+    }
+}
+
+/// The `[cookie]` table of the day 16 config
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub(super) struct CookieConfig {
+    name: String,
+    path: String,
+    refresh_name: String,
+    /// The refresh token is only sent to `/16/refresh`
+    refresh_path: String,
+    http_only: bool,
+    secure: bool,
+    same_site: SameSiteConfig,
+    mode: Mode,
+    /// At least 64 bytes, required by the signed and private modes
+    key: Option<String>,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        CookieConfig {
+            name: "gift".to_string(),
+            path: "/16".to_string(),
+            refresh_name: "gift_refresh".to_string(),
+            refresh_path: "/16/refresh".to_string(),
+            http_only: true,
+            secure: true,
+            same_site: SameSiteConfig::Strict,
+            mode: Mode::Plain,
+            key: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(super) enum Kind {
+    Access,
+    Refresh,
+}
+
+/// Sets, reads and clears the gift cookies
+#[derive(Debug, Default)]
+pub(super) struct GiftCookie {
+    config: CookieConfig,
+    key: Option<Key>,
+}
+
+impl GiftCookie {
+    pub(super) fn new(config: CookieConfig) -> Result<GiftCookie, String> {
+        let key = match (config.mode, &config.key) {
+            (Mode::Plain, _) => None,
+            (mode, Some(key)) => Some(
+                Key::try_from(key.as_bytes())
+                    .map_err(|err| format!("Invalid {mode:?} cookie key: {err}"))?,
+            ),
+            (mode, None) => return Err(format!("The {mode:?} cookie mode needs a key")),
+        };
+
+        Ok(GiftCookie { config, key })
+    }
+
+    fn name(&self, kind: Kind) -> &str {
+        match kind {
+            Kind::Access => &self.config.name,
+            Kind::Refresh => &self.config.refresh_name,
+        }
+    }
+
+    fn cookie(&self, kind: Kind, value: String) -> Cookie<'static> {
+        let path = match kind {
+            Kind::Access => &self.config.path,
+            Kind::Refresh => &self.config.refresh_path,
+        };
+
+        Cookie::build((self.name(kind).to_string(), value))
+            .path(path.clone())
+            .http_only(self.config.http_only)
+            .secure(self.config.secure)
+            .same_site(self.config.same_site.into())
+            .build()
+    }
+
+    /// Respond with the `tokens` set, each expiring with it after its max age in seconds
+    pub(super) fn set(&self, headers: &HeaderMap, tokens: Vec<(Kind, String, u64)>) -> Response {
+        let cookies = tokens.into_iter().map(|(kind, token, max_age)| {
+            let mut cookie = self.cookie(kind, token);
+            cookie.set_max_age(Duration::seconds(max_age.try_into().unwrap_or(i64::MAX)));
+            cookie
+        });
+
+        match (self.config.mode, self.key.clone()) {
+            (Mode::Signed, Some(key)) => cookies
+                .fold(
+                    SignedCookieJar::from_headers(headers, key),
+                    |jar, cookie| jar.add(cookie),
+                )
+                .into_response(),
+            (Mode::Private, Some(key)) => cookies
+                .fold(
+                    PrivateCookieJar::from_headers(headers, key),
+                    |jar, cookie| jar.add(cookie),
+                )
+                .into_response(),
+            _ => cookies
+                .fold(CookieJar::from_headers(headers), |jar, cookie| {
+                    jar.add(cookie)
+                })
+                .into_response(),
+        }
+    }
+
+    /// The token, 400 when the cookie is missing and 401 when it was tampered with
+    pub(super) fn get(&self, headers: &HeaderMap, kind: Kind) -> Result<String, StatusCode> {
+        let name = self.name(kind);
+        if CookieJar::from_headers(headers).get(name).is_none() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let cookie = match (self.config.mode, self.key.clone()) {
+            (Mode::Signed, Some(key)) => SignedCookieJar::from_headers(headers, key).get(name),
+            (Mode::Private, Some(key)) => PrivateCookieJar::from_headers(headers, key).get(name),
+            _ => CookieJar::from_headers(headers).get(name).cloned(),
+        };
+
+        cookie
+            .map(|cookie| cookie.value().to_string())
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+
+    /// Respond with both cookies cleared, even if the refresh cookie wasn't sent to this path
+    pub(super) fn clear(&self, headers: &HeaderMap) -> Response {
+        [Kind::Access, Kind::Refresh]
+            .into_iter()
+            .map(|kind| {
+                let mut cookie = self.cookie(kind, String::new());
+                cookie.make_removal();
+                cookie
+            })
+            .fold(CookieJar::from_headers(headers), |jar, cookie| {
+                jar.add(cookie)
+            })
+            .into_response()
+    }
+}
@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+use jsonwebtoken::Algorithm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::{jwe, GiftState};
+
+/// Claims rendered as dates
+const TIMESTAMPS: [&str; 3] = ["iat", "nbf", "exp"];
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub(super) struct Inspection {
+    header: Option<Value>,
+    payload: Option<Value>,
+    algorithm: Option<String>,
+    kid: Option<String>,
+    encrypted: bool,
+    timestamps: BTreeMap<String, String>,
+    problems: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<Verification>,
+}
+
+/// `valid`, or why verification failed
+#[derive(Serialize, Debug, PartialEq)]
+struct Verification {
+    /// Against our own keys, as `/16/unwrap`
+    own: String,
+    /// Against the trusted JWKS, as `/16/decode`
+    trusted: String,
+}
+
+/// Base64url decode a JSON object, recording what's wrong with it
+fn part(name: &str, part: &str, problems: &mut Vec<String>) -> Option<Value> {
+    let bytes = match URL_SAFE_NO_PAD.decode(part) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            problems.push(format!("The {name} is not base64url encoded: {err}"));
+            return None;
+        }
+    };
+    let value = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => value,
+        Err(err) => {
+            problems.push(format!("The {name} is not JSON: {err}"));
+            return None;
+        }
+    };
+    if !value.is_object() {
+        problems.push(format!("The {name} is not a JSON object"));
+    }
+
+    Some(value)
+}
+
+impl Inspection {
+    /// Decode `token` without verifying it
+    fn new(token: &str, now: DateTime<Utc>) -> Inspection {
+        let mut inspection = Inspection::default();
+        let problems = &mut inspection.problems;
+        let token = token.trim();
+        let parts = token.split('.').collect::<Vec<_>>();
+        inspection.encrypted = jwe::is_jwe(token);
+        if parts.len() != 3 && !inspection.encrypted {
+            problems.push(format!(
+                "Expected 3 parts separated by `.`, found {}",
+                parts.len()
+            ));
+        }
+
+        inspection.header = part("header", parts[0], problems);
+        if let Some(header) = &inspection.header {
+            match header.get("alg").and_then(Value::as_str) {
+                None => problems.push("The header has no `alg`".to_string()),
+                Some("none") => problems.push("Unsigned tokens are not accepted".to_string()),
+                Some(alg) if inspection.encrypted => inspection.algorithm = Some(alg.to_string()),
+                Some(alg) => match Algorithm::from_str(alg) {
+                    Ok(algorithm) => inspection.algorithm = Some(format!("{algorithm:?}")),
+                    Err(_) => problems.push(format!("Unsupported algorithm `{alg}`")),
+                },
+            }
+            match header.get("typ").and_then(Value::as_str) {
+                Some(typ) if !typ.eq_ignore_ascii_case("JWT") => {
+                    problems.push(format!("Unexpected type `{typ}`"));
+                }
+                _ => {}
+            }
+            inspection.kid = header
+                .get("kid")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+        }
+
+        if inspection.encrypted {
+            debug!("The payload is encrypted");
+            return inspection;
+        }
+
+        if let Some(payload) = parts.get(1) {
+            inspection.payload = part("payload", payload, problems);
+        }
+        match parts.get(2) {
+            Some(&"") => problems.push("The signature is empty".to_string()),
+            Some(signature) if URL_SAFE_NO_PAD.decode(signature).is_err() => {
+                problems.push("The signature is not base64url encoded".to_string());
+            }
+            _ => {}
+        }
+
+        let payload = inspection.payload.as_ref().and_then(Value::as_object);
+        for claim in TIMESTAMPS {
+            let Some(value) = payload.and_then(|payload| payload.get(claim)) else {
+                continue;
+            };
+            let Some(date) = value
+                .as_i64()
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            else {
+                problems.push(format!("`{claim}` is not a timestamp"));
+                continue;
+            };
+
+            match claim {
+                "exp" if date < now => problems.push("The token has expired".to_string()),
+                "nbf" if date > now => problems.push("The token is not valid yet".to_string()),
+                _ => {}
+            }
+            inspection.timestamps.insert(
+                claim.to_string(),
+                date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            );
+        }
+
+        inspection
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(super) struct InspectOptions {
+    /// Also verify the token against the configured keys
+    #[serde(default)]
+    verify: bool,
+}
+
+/// Decode a token without verifying it, to find out what's wrong with it
+pub(super) async fn inspect(
+    State(state): State<GiftState>,
+    Query(options): Query<InspectOptions>,
+    token: String,
+) -> Json<Inspection> {
+    debug!("Calling inspect");
+
+    let mut inspection = Inspection::new(&token, Utc::now());
+    if options.verify {
+        let token = token.trim();
+        let own = match inspection.encrypted {
+            true => state.keys.decrypt(token),
+            false => Ok(token.to_string()),
+        }
+        .and_then(|token| state.keys.decode::<Value>(&token));
+        let result = |result: Result<_, super::KeyError>| match result {
+            Ok(_) => "valid".to_string(),
+            Err(err) => err.to_text(),
+        };
+
+        inspection.verification = Some(Verification {
+            own: result(own),
+            trusted: result(state.keys.verify::<Value>(token)),
+        });
+    }
+
+    Json(inspection)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn encode(value: Value) -> String {
+        URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    #[rstest::rstest]
+    #[case::valid(
+        format!("{}.{}.c2ln", encode(json!({"alg": "HS256", "kid": "2024"})), encode(json!({"exp": 1_734_000_000}))),
+        vec![]
+    )]
+    #[case::parts("abc", vec!["Expected 3 parts separated by `.`, found 1", "The header is not JSON: expected value at line 1 column 1"])]
+    #[case::header(
+        format!("!!.{}.c2ln", encode(json!({}))),
+        vec!["The header is not base64url encoded: Invalid symbol 33, offset 0."]
+    )]
+    #[case::algorithm(
+        format!("{}.{}.", encode(json!({"alg": "none", "typ": "JWS"})), encode(json!([]))),
+        vec!["Unsigned tokens are not accepted", "Unexpected type `JWS`", "The payload is not a JSON object", "The signature is empty"]
+    )]
+    #[case::claims(
+        format!("{}.{}.c2ln", encode(json!({"alg": "HS384"})), encode(json!({"exp": 1_700_000_000, "nbf": "soon", "iat": 1_800_000_000}))),
+        vec!["`nbf` is not a timestamp", "The token has expired"]
+    )]
+    #[test_log::test]
+    fn test_problems(#[case] token: String, #[case] problems: Vec<&str>) {
+        let now = DateTime::from_timestamp(1_733_000_000, 0).unwrap();
+
+        let inspection = Inspection::new(&token, now);
+
+        assert_eq!(problems, inspection.problems);
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_inspection() {
+        let token = format!(
+            "{}.{}.c2ln",
+            encode(json!({"alg": "RS256", "typ": "JWT", "kid": "santa"})),
+            encode(json!({"gift": true, "iat": 1_733_000_000, "exp": 1_734_000_000}))
+        );
+        let now = DateTime::from_timestamp(1_733_000_000, 0).unwrap();
+
+        let inspection = Inspection::new(&token, now);
+
+        assert_eq!(
+            json!({
+                "header": {"alg": "RS256", "typ": "JWT", "kid": "santa"},
+                "payload": {"gift": true, "iat": 1_733_000_000, "exp": 1_734_000_000},
+                "algorithm": "RS256",
+                "kid": "santa",
+                "encrypted": false,
+                "timestamps": {"iat": "2024-11-30T20:53:20Z", "exp": "2024-12-12T10:40:00Z"},
+                "problems": [],
+            }),
+            serde_json::to_value(inspection).unwrap()
+        );
+    }
+}
@@ -0,0 +1,805 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    errors::ErrorKind,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk,
+        JwkSet, KeyAlgorithm, OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
+use rand::Rng;
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::{debug, info, warn};
+
+use super::{
+    claims::Claims,
+    cookie::{CookieConfig, GiftCookie},
+    jwe::{Encryption, EncryptionConfig},
+};
+
+/// Key entries, e.g. from the `DAY16_KEYS` secret or `config/day_16.toml`
+#[derive(Deserialize, Debug)]
+struct KeysConfig {
+    #[serde(default)]
+    keys: Vec<KeyConfig>,
+    /// JWKS document of partner keys trusted by `/16/decode`, a path or `file://` URL
+    jwks: Option<String>,
+    #[serde(default)]
+    claims: Claims,
+    #[serde(default)]
+    cookie: CookieConfig,
+    /// Key for `/16/wrap?encrypt=true`
+    encryption: Option<EncryptionConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KeyConfig {
+    kid: String,
+    #[serde(default)]
+    algorithm: Algorithm,
+    /// Shared secret for `HS*` keys
+    secret: Option<String>,
+    /// PEM encoded PKCS#8 private key for the other algorithms
+    private_key: Option<String>,
+    private_key_file: Option<PathBuf>,
+    /// PEM encoded public key, only used to verify
+    public_key: Option<String>,
+    public_key_file: Option<PathBuf>,
+    /// Tokens signed with the key are refused afterwards, a toml offset date-time
+    expires: Option<toml::value::Datetime>,
+}
+
+/// Inline PEM, or a file relative to the config
+fn pem(
+    inline: Option<String>,
+    file: Option<PathBuf>,
+    base: &Path,
+) -> Result<Option<String>, String> {
+    match (inline, file) {
+        (Some(pem), _) => Ok(Some(pem)),
+        (None, Some(file)) => std::fs::read_to_string(base.join(&file))
+            .map(Some)
+            .map_err(|err| format!("Unable to read {}: {err}", file.display())),
+        (None, None) => Ok(None),
+    }
+}
+
+struct Key {
+    kid: String,
+    algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    /// Public part of asymmetric keys
+    jwk: Option<Jwk>,
+    expires: Option<DateTime<Utc>>,
+}
+
+impl Key {
+    fn load(config: KeyConfig, base: &Path) -> Result<Key, String> {
+        let KeyConfig {
+            kid,
+            algorithm,
+            secret,
+            private_key,
+            private_key_file,
+            public_key,
+            public_key_file,
+            expires,
+        } = config;
+
+        let private_key = pem(private_key, private_key_file, base)?;
+        let public_key = pem(public_key, public_key_file, base)?;
+        let expires = expires
+            .map(|expires| DateTime::parse_from_rfc3339(&expires.to_string()))
+            .transpose()
+            .map_err(|err| format!("Key `{kid}` has an invalid expiry: {err}"))?
+            .map(|expires| expires.to_utc());
+
+        let invalid = |err: String| format!("Key `{kid}` is invalid: {err}");
+        let (encoding, decoding, jwk) = match (family(algorithm), secret, private_key, public_key) {
+            (Family::Hmac, Some(secret), _, _) => (
+                Some(EncodingKey::from_secret(secret.as_bytes())),
+                DecodingKey::from_secret(secret.as_bytes()),
+                None,
+            ),
+            (Family::Hmac, None, _, _) => {
+                return Err(format!("Key `{kid}` is missing its secret"));
+            }
+            (family, _, Some(pem), _) => {
+                let encoding = match family {
+                    Family::Rsa => EncodingKey::from_rsa_pem(pem.as_bytes()),
+                    Family::Ec => EncodingKey::from_ec_pem(pem.as_bytes()),
+                    _ => EncodingKey::from_ed_pem(pem.as_bytes()),
+                }
+                .map_err(|err| invalid(err.to_string()))?;
+                let jwk = public_jwk(&kid, algorithm, &pem, true).map_err(invalid)?;
+                let decoding =
+                    DecodingKey::from_jwk(&jwk).map_err(|err| invalid(err.to_string()))?;
+                (Some(encoding), decoding, Some(jwk))
+            }
+            (_, _, None, Some(pem)) => {
+                let jwk = public_jwk(&kid, algorithm, &pem, false).map_err(invalid)?;
+                let decoding =
+                    DecodingKey::from_jwk(&jwk).map_err(|err| invalid(err.to_string()))?;
+                (None, decoding, Some(jwk))
+            }
+            (_, _, None, None) => {
+                return Err(format!("Key `{kid}` is missing its {algorithm:?} key"));
+            }
+        };
+
+        Ok(Key {
+            kid,
+            algorithm,
+            encoding,
+            decoding,
+            jwk,
+            expires,
+        })
+    }
+
+    /// A verify-only key from a JWKS document, the algorithm is guessed from the key type if unset
+    fn trust(jwk: &Jwk) -> Result<Key, String> {
+        let algorithm = match (&jwk.common.key_algorithm, &jwk.algorithm) {
+            (Some(algorithm), _) => format!("{algorithm:?}")
+                .parse::<Algorithm>()
+                .map_err(|err| err.to_string())?,
+            (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+            (None, AlgorithmParameters::EllipticCurve(params)) => match params.curve {
+                EllipticCurve::P384 => Algorithm::ES384,
+                _ => Algorithm::ES256,
+            },
+            (None, AlgorithmParameters::OctetKeyPair(_)) => Algorithm::EdDSA,
+            (None, AlgorithmParameters::OctetKey(_)) => Algorithm::HS256,
+        };
+
+        Ok(Key {
+            kid: jwk.common.key_id.clone().unwrap_or_default(),
+            algorithm,
+            encoding: None,
+            decoding: DecodingKey::from_jwk(jwk).map_err(|err| err.to_string())?,
+            jwk: None,
+            expires: None,
+        })
+    }
+
+    /// A random `HS256` key, for when none is configured
+    fn ephemeral() -> Key {
+        let secret = rand::thread_rng().gen::<[u8; 32]>();
+        Key {
+            kid: "ephemeral".to_string(),
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(&secret)),
+            decoding: DecodingKey::from_secret(&secret),
+            jwk: None,
+            expires: None,
+        }
+    }
+
+    fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_none_or(|expires| now < expires)
+    }
+
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(self.algorithm);
+        validation.set_required_spec_claims::<&str>(&[]);
+        validation.validate_nbf = true;
+        validation.algorithms = family(self.algorithm).algorithms().to_vec();
+
+        validation
+    }
+}
+
+/// The public JWK of a PEM encoded private or public key
+fn public_jwk(kid: &str, algorithm: Algorithm, pem: &str, private: bool) -> Result<Jwk, String> {
+    use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use rsa::traits::PublicKeyParts;
+
+    let encode = |bytes: &[u8]| URL_SAFE_NO_PAD.encode(bytes);
+    fn message(err: impl std::fmt::Display) -> String {
+        err.to_string()
+    }
+
+    let ec = |curve, point: &[u8]| {
+        // uncompressed sec1 points are 0x04 || x || y
+        let (x, y) = point[1..].split_at((point.len() - 1) / 2);
+        AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+            curve,
+            x: encode(x),
+            y: encode(y),
+            ..Default::default()
+        })
+    };
+
+    let parameters = match algorithm {
+        Algorithm::ES256 => {
+            let key = match private {
+                true => p256::SecretKey::from_pkcs8_pem(pem)
+                    .map(|key| key.public_key())
+                    .map_err(message),
+                false => p256::PublicKey::from_public_key_pem(pem).map_err(message),
+            }?;
+            ec(EllipticCurve::P256, key.to_encoded_point(false).as_bytes())
+        }
+        Algorithm::ES384 => {
+            let key = match private {
+                true => p384::SecretKey::from_pkcs8_pem(pem)
+                    .map(|key| key.public_key())
+                    .map_err(message),
+                false => p384::PublicKey::from_public_key_pem(pem).map_err(message),
+            }?;
+            ec(EllipticCurve::P384, key.to_encoded_point(false).as_bytes())
+        }
+        Algorithm::EdDSA => {
+            let key = match private {
+                true => ed25519_dalek::SigningKey::from_pkcs8_pem(pem)
+                    .map(|key| key.verifying_key())
+                    .map_err(message),
+                false => ed25519_dalek::VerifyingKey::from_public_key_pem(pem).map_err(message),
+            }?;
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                curve: EllipticCurve::Ed25519,
+                x: encode(key.as_bytes()),
+                ..Default::default()
+            })
+        }
+        _ => {
+            use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+
+            // `BEGIN RSA ...` labels are PKCS#1, the others PKCS#8
+            let pkcs1 = pem.trim_start().starts_with("-----BEGIN RSA ");
+            let key = match (private, pkcs1) {
+                (true, true) => rsa::RsaPrivateKey::from_pkcs1_pem(pem)
+                    .map(|key| key.to_public_key())
+                    .map_err(message),
+                (true, false) => rsa::RsaPrivateKey::from_pkcs8_pem(pem)
+                    .map(|key| key.to_public_key())
+                    .map_err(message),
+                (false, true) => rsa::RsaPublicKey::from_pkcs1_pem(pem).map_err(message),
+                (false, false) => rsa::RsaPublicKey::from_public_key_pem(pem).map_err(message),
+            }?;
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                n: encode(&key.n().to_bytes_be()),
+                e: encode(&key.e().to_bytes_be()),
+                ..Default::default()
+            })
+        }
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: format!("{algorithm:?}").parse::<KeyAlgorithm>().ok(),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Family {
+    Hmac,
+    Rsa,
+    Ec,
+    Ed,
+}
+
+impl Family {
+    /// Algorithms that can be verified with the same key
+    fn algorithms(&self) -> &'static [Algorithm] {
+        match self {
+            Family::Hmac => &[Algorithm::HS256, Algorithm::HS384, Algorithm::HS512],
+            Family::Rsa => &[
+                Algorithm::RS256,
+                Algorithm::RS384,
+                Algorithm::RS512,
+                Algorithm::PS256,
+                Algorithm::PS384,
+                Algorithm::PS512,
+            ],
+            Family::Ec => &[Algorithm::ES256, Algorithm::ES384],
+            Family::Ed => &[Algorithm::EdDSA],
+        }
+    }
+}
+
+fn family(algorithm: Algorithm) -> Family {
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => Family::Hmac,
+        Algorithm::ES256 | Algorithm::ES384 => Family::Ec,
+        Algorithm::EdDSA => Family::Ed,
+        _ => Family::Rsa,
+    }
+}
+
+/// Read a JWKS document from a path relative to `base`, or a `file://` URL
+fn read_jwks(location: &str, base: &Path) -> Result<JwkSet, String> {
+    let path = match location.split_once("://") {
+        Some(("file", path)) => PathBuf::from(path),
+        Some((scheme, _)) => return Err(format!("Unsupported JWKS scheme `{scheme}`")),
+        None => base.join(location),
+    };
+    let document = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Unable to read {}: {err}", path.display()))?;
+
+    serde_json::from_str(&document).map_err(|err| err.to_string())
+}
+
+/// Our keys in the order they were added, the newest valid key that can sign is used for new
+/// tokens while older ones keep verifying until they expire. Partner keys are kept separately.
+pub struct KeyRing {
+    keys: Vec<Key>,
+    trusted: Vec<Key>,
+    pub(super) claims: Claims,
+    pub(super) cookie: GiftCookie,
+    encryption: Option<Encryption>,
+}
+
+impl KeyRing {
+    /// Parse keys from toml, key files and the JWKS document are relative to `base`
+    pub fn from_toml(config: &str, base: impl AsRef<Path>) -> Result<KeyRing, String> {
+        let base = base.as_ref();
+        let config: KeysConfig = toml::from_str(config).map_err(|err| err.to_string())?;
+        let keys = config
+            .keys
+            .into_iter()
+            .map(|key| Key::load(key, base))
+            .collect::<Result<Vec<_>, _>>()?;
+        info!(kids = ?keys.iter().map(|key| &key.kid).collect::<Vec<_>>(), "Loaded keys");
+
+        let trusted = match config.jwks {
+            Some(location) => read_jwks(&location, base)?,
+            None => JwkSet { keys: vec![] },
+        };
+
+        Ok(KeyRing {
+            keys,
+            trusted: KeyRing::trust(&trusted),
+            claims: config.claims,
+            cookie: GiftCookie::new(config.cookie)?,
+            encryption: config.encryption.map(Encryption::new).transpose()?,
+        })
+    }
+
+    fn trust(jwks: &JwkSet) -> Vec<Key> {
+        jwks.keys
+            .iter()
+            .filter_map(|jwk| match Key::trust(jwk) {
+                Ok(key) => Some(key),
+                Err(err) => {
+                    warn!(?err, kid = ?jwk.common.key_id, "Ignoring JWK");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Read the config file at `path`, with the entries of `secret`, e.g. from Shuttle secrets,
+    /// merged over it. Tables are merged by key, so the secret can hold the `[[keys]]`, the
+    /// `[encryption]` key and the cookie `key` while the rest comes from the file. A random key
+    /// signs when none is configured, the encryption key only comes from `secret`.
+    pub fn load(secret: Option<String>, path: impl AsRef<Path>) -> KeyRing {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new("."));
+        let file = std::fs::read_to_string(path).unwrap_or_else(|err| {
+            warn!(?path, ?err, "Unable to read day 16 keys");
+            String::new()
+        });
+        let mut config: toml::Table = toml::from_str(&file).expect("Invalid day 16 keys");
+        if config.remove("encryption").is_some() {
+            warn!(
+                ?path,
+                "Ignoring the day 16 encryption key, set the `DAY16_KEYS` secret"
+            );
+        }
+        if let Some(secret) = secret {
+            let secret: toml::Table = toml::from_str(&secret).expect("Invalid `DAY16_KEYS`");
+            merge(&mut config, secret);
+        }
+
+        let mut keys = KeyRing::from_toml(&config.to_string(), base).expect("Invalid day 16 keys");
+        if keys.signing_key(Utc::now()).is_none() {
+            warn!("No day 16 signing key, set the `DAY16_KEYS` secret. Using a random key");
+            keys.keys.push(Key::ephemeral());
+        }
+
+        keys
+    }
+
+    /// The newest key that can sign
+    fn signing_key(&self, now: DateTime<Utc>) -> Option<&Key> {
+        self.keys
+            .iter()
+            .rev()
+            .find(|key| key.encoding.is_some() && key.is_valid(now))
+    }
+
+    pub(super) fn encode<T: serde::Serialize>(&self, claims: &T) -> Result<String, KeyError> {
+        let key = self.signing_key(Utc::now()).ok_or(KeyError::NoSigningKey)?;
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+
+        let encoding = key.encoding.as_ref().ok_or(KeyError::NoSigningKey)?;
+        Ok(jsonwebtoken::encode(&header, claims, encoding)?)
+    }
+
+    /// Verify one of our tokens, including its registered claims
+    pub(super) fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<TokenData<T>, KeyError> {
+        self.decode_checked(token, &self.claims)
+    }
+
+    /// Verify one of our tokens issued for `audience` rather than the configured one
+    pub(super) fn decode_for<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        audience: &str,
+    ) -> Result<TokenData<T>, KeyError> {
+        let claims = Claims {
+            audience: Some(audience.to_string()),
+            ..self.claims.clone()
+        };
+        self.decode_checked(token, &claims)
+    }
+
+    fn decode_checked<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        claims: &Claims,
+    ) -> Result<TokenData<T>, KeyError> {
+        let data = decode_with::<serde_json::Value>(&self.keys, token, Some(claims))?;
+        Claims::require(&data.claims).map_err(KeyError::Jwt)?;
+        let claims =
+            serde_json::from_value(data.claims).map_err(|err| KeyError::Jwt(err.into()))?;
+
+        Ok(TokenData {
+            header: data.header,
+            claims,
+        })
+    }
+
+    /// Verify a partner token against the trusted JWKS
+    pub(super) fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<TokenData<T>, KeyError> {
+        decode_with(&self.trusted, token, None)
+    }
+
+    /// Nest a signed token in a JWE, `None` without an encryption key
+    pub(super) fn encrypt(&self, jws: &str) -> Option<Result<String, KeyError>> {
+        self.encryption
+            .as_ref()
+            .map(|encryption| encryption.encrypt(jws))
+    }
+
+    /// The signed token nested in `jwe`
+    pub(super) fn decrypt(&self, jwe: &str) -> Result<String, KeyError> {
+        self.encryption
+            .as_ref()
+            .ok_or(KeyError::UnknownKey)?
+            .decrypt(jwe)
+    }
+
+    /// `aud` of gift tokens
+    pub fn audience(&self) -> Option<&str> {
+        self.claims.audience.as_deref()
+    }
+
+    /// Public keys of our asymmetric keys that haven't expired
+    pub(super) fn jwks(&self) -> JwkSet {
+        let now = Utc::now();
+        JwkSet {
+            keys: self
+                .keys
+                .iter()
+                .filter(|key| key.is_valid(now))
+                .filter_map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Replace the entries of `config` with those of `other`, merging tables
+fn merge(config: &mut toml::Table, other: toml::Table) {
+    for (name, value) in other {
+        match (config.get_mut(&name), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(other)) => merge(table, other),
+            (_, value) => {
+                config.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Verify with the key named by `kid`, or every valid key of the token's algorithm without one
+fn decode_with<T: DeserializeOwned>(
+    keys: &[Key],
+    token: &str,
+    claims: Option<&Claims>,
+) -> Result<TokenData<T>, KeyError> {
+    let header = jsonwebtoken::decode_header(token)?;
+    debug!(?header);
+
+    let now = Utc::now();
+    let candidates = keys.iter().filter(|key| match &header.kid {
+        Some(kid) => &key.kid == kid,
+        None => family(key.algorithm) == family(header.alg),
+    });
+
+    let mut result = Err(KeyError::UnknownKey);
+    for key in candidates {
+        if !key.is_valid(now) {
+            result = Err(KeyError::Expired(key.kid.clone()));
+            continue;
+        }
+
+        let mut validation = key.validation();
+        if let Some(claims) = claims {
+            claims.configure(&mut validation);
+        }
+        result = jsonwebtoken::decode(token, &key.decoding, &validation).map_err(KeyError::Jwt);
+        if result.is_ok() {
+            break;
+        }
+    }
+
+    result
+}
+
+#[derive(Debug)]
+pub(super) enum KeyError {
+    NoSigningKey,
+    /// No configured key matches the token
+    UnknownKey,
+    /// The token was signed with a key that has expired
+    Expired(String),
+    /// The token's `jti` was revoked
+    Revoked,
+    Jwt(jsonwebtoken::errors::Error),
+}
+
+impl KeyError {
+    pub(super) fn to_text(&self) -> String {
+        match self {
+            KeyError::NoSigningKey => "No key can sign".to_string(),
+            KeyError::UnknownKey => "No configured key matches the token".to_string(),
+            KeyError::Expired(kid) => format!("The key {kid} has expired"),
+            KeyError::Revoked => "The token has been revoked".to_string(),
+            KeyError::Jwt(err) => match err.kind() {
+                ErrorKind::ExpiredSignature => "The token has expired".to_string(),
+                ErrorKind::ImmatureSignature => "The token is not valid yet".to_string(),
+                _ => err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for KeyError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        KeyError::Jwt(err)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Mirrors `config/day_16.toml` with extra rotated keys
+    pub(crate) const KEYS: &str = r#"
+jwks = "day_16_jwks.json"
+
+[claims]
+issuer = "north-pole"
+audience = "gifts"
+
+[encryption]
+kid = "2024"
+key = "aUQGHQLgWF_lUwDJ5pD16CS_nBrW9B1r4mQBmsbyHHM"
+
+[[keys]]
+kid = "expired"
+secret = "EXPIRED"
+expires = 2024-01-01T00:00:00Z
+
+[[keys]]
+kid = "2024"
+secret = "SECRET"
+
+[[keys]]
+kid = "2025"
+algorithm = "HS512"
+secret = "NEWER SECRET"
+"#;
+
+    pub(crate) fn keys() -> KeyRing {
+        KeyRing::from_toml(KEYS, "config").unwrap()
+    }
+
+    fn token(kid: &str, secret: &str) -> String {
+        let header = Header {
+            kid: Some(kid.to_string()),
+            ..Default::default()
+        };
+        let claims = keys()
+            .claims
+            .issue(json!({"gift": true}), "rudolph", None, Utc::now())
+            .unwrap();
+        jsonwebtoken::encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_rotation() {
+        let keys = keys();
+
+        let claims = keys
+            .claims
+            .issue(json!({"gift": true}), "rudolph", None, Utc::now())
+            .unwrap();
+        let token = keys.encode(&claims).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+
+        assert_eq!(Some("2025".to_string()), header.kid);
+        assert_eq!(Algorithm::HS512, header.alg);
+        assert_eq!(claims, keys.decode::<Value>(&token).unwrap().claims);
+    }
+
+    #[rstest::rstest]
+    #[case::previous_key(token("2024", "SECRET"), true)]
+    #[case::expired_key(token("expired", "EXPIRED"), false)]
+    #[case::unknown_key(token("2023", "SECRET"), false)]
+    #[case::wrong_secret(token("2024", "NEWER SECRET"), false)]
+    #[test_log::test]
+    fn test_decode(#[case] token: String, #[case] valid: bool) {
+        assert_eq!(valid, keys().decode::<Value>(&token).is_ok());
+    }
+
+    /// Test only keys, see `test_keys/`
+    const ASYMMETRIC: &str = r#"
+[[keys]]
+kid = "rsa"
+algorithm = "RS256"
+private_key_file = "rsa.pem"
+
+[[keys]]
+kid = "ec"
+algorithm = "ES256"
+private_key_file = "ec.pem"
+
+[[keys]]
+kid = "ed"
+algorithm = "EdDSA"
+private_key_file = "ed.pem"
+"#;
+
+    #[rstest::rstest]
+    #[case::rsa("rsa", "RSA")]
+    #[case::ec("ec", "EC")]
+    #[case::ed("ed", "OKP")]
+    #[test_log::test]
+    fn test_jwks(#[case] kid: &str, #[case] kty: &str) {
+        // Only keep the keys up to `kid` so it signs
+        let end = ASYMMETRIC
+            .find(&format!("kid = \"{kid}\""))
+            .and_then(|start| ASYMMETRIC[start..].find("[[keys]]").map(|end| start + end))
+            .unwrap_or(ASYMMETRIC.len());
+        let keys = KeyRing::from_toml(&ASYMMETRIC[..end], "src/day_16/test_keys").unwrap();
+
+        let token = keys.encode(&json!({"gift": true})).unwrap();
+        let jwks = keys.jwks();
+        let jwk = serde_json::to_value(jwks.find(kid).unwrap()).unwrap();
+        assert_eq!(kty, jwk["kty"]);
+        assert_eq!("sig", jwk["use"]);
+
+        let partner = KeyRing {
+            keys: vec![],
+            trusted: KeyRing::trust(&jwks),
+            claims: Claims::default(),
+            cookie: GiftCookie::default(),
+            encryption: None,
+        };
+        assert_eq!(
+            json!({"gift": true}),
+            partner.verify::<Value>(&token).unwrap().claims
+        );
+        assert!(partner.decode::<Value>(&token).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case::private("private_key_file = \"rsa_pkcs1.pem\"")]
+    #[case::public("public_key_file = \"rsa_pkcs1.pub.pem\"")]
+    #[test_log::test]
+    fn test_pkcs1(#[case] key: &str) {
+        let config = format!("[[keys]]\nkid = \"rsa\"\nalgorithm = \"RS256\"\n{key}");
+        let keys = KeyRing::from_toml(&config, "src/day_16/test_keys").unwrap();
+        let pkcs8 = KeyRing::from_toml(ASYMMETRIC, "src/day_16/test_keys").unwrap();
+
+        assert_eq!(
+            serde_json::to_value(pkcs8.jwks().find("rsa")).unwrap(),
+            serde_json::to_value(keys.jwks().find("rsa")).unwrap()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::path("day_16_jwks.json", true)]
+    #[case::url(&format!("file://{}/config/day_16_jwks.json", env!("CARGO_MANIFEST_DIR")), true)]
+    #[case::remote("https://example.com/jwks.json", false)]
+    #[test_log::test]
+    fn test_read_jwks(#[case] location: &str, #[case] ok: bool) {
+        assert_eq!(ok, read_jwks(location, Path::new("config")).is_ok());
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_shipped_config() {
+        let config: KeysConfig =
+            toml::from_str(&std::fs::read_to_string("config/day_16.toml").unwrap()).unwrap();
+        assert!(
+            config.keys.is_empty() && config.encryption.is_none(),
+            "Keys belong in the `DAY16_KEYS` secret"
+        );
+
+        let keys = KeyRing::load(None, "config/day_16.toml");
+
+        assert!(keys.encrypt("gift").is_none());
+        let key = keys.signing_key(Utc::now()).unwrap();
+        assert_eq!("ephemeral", key.kid);
+        assert_eq!(1, keys.trusted.len());
+        let token = keys.encode(&json!({"gift": true})).unwrap();
+        assert!(KeyRing::load(None, "config/day_16.toml")
+            .decode::<Value>(&token)
+            .is_err());
+    }
+
+    #[rstest::rstest]
+    #[test_log::test]
+    fn test_load_secret() {
+        let secret = r#"
+[cookie]
+mode = "signed"
+key = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+
+[encryption]
+kid = "2024"
+key = "aUQGHQLgWF_lUwDJ5pD16CS_nBrW9B1r4mQBmsbyHHM"
+
+[[keys]]
+kid = "2025"
+algorithm = "HS512"
+secret = "NEWER SECRET"
+"#;
+        let keys = KeyRing::load(Some(secret.to_string()), "config/day_16.toml");
+
+        assert_eq!("2025", keys.signing_key(Utc::now()).unwrap().kid);
+        assert!(keys.encrypt("gift").is_some());
+        // the rest of the config file is kept
+        assert_eq!(Some("gifts"), keys.audience());
+        assert_eq!(Some("north-pole"), keys.claims.issuer.as_deref());
+        assert_eq!(86400, keys.claims.max_ttl);
+        assert_eq!(1, keys.trusted.len());
+        let response = keys.cookie.set(
+            &axum::http::HeaderMap::new(),
+            vec![(super::super::cookie::Kind::Access, "gift".to_string(), 60)],
+        );
+        let cookie = response.headers()[axum::http::header::SET_COOKIE]
+            .to_str()
+            .unwrap();
+        assert!(cookie.starts_with("gift=") && !cookie.starts_with("gift=gift;"));
+        assert!(cookie.contains("Path=/16"));
+    }
+}
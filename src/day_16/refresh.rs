@@ -0,0 +1,212 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::{access_token, cookie::Kind, GiftState};
+
+/// Refresh tokens are random, only their hash is stored
+fn generate() -> (String, String) {
+    let token = URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>());
+    let hash = hash(&token);
+
+    (token, hash)
+}
+
+pub(super) fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// What a refresh token was issued for, carried over on rotation
+#[derive(Debug)]
+pub(super) struct Grant {
+    pub(super) family: Uuid,
+    pub(super) gift: Value,
+    pub(super) encrypted: bool,
+    pub(super) expires_at: DateTime<Utc>,
+}
+
+/// Store a new refresh token for `grant`
+pub(super) async fn issue(
+    executor: impl sqlx::PgExecutor<'_>,
+    grant: &Grant,
+) -> Result<String, sqlx::Error> {
+    let (token, hash) = generate();
+    let subject = grant.family.to_string();
+    sqlx::query!(
+        r#"INSERT INTO gift_refresh_tokens (hash, family, subject, gift, encrypted, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)"#,
+        hash,
+        grant.family,
+        subject,
+        grant.gift,
+        grant.encrypted,
+        grant.expires_at
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(token)
+}
+
+#[derive(Debug)]
+enum Exchange {
+    /// The token was swapped for the returned one
+    Rotated(Grant, String),
+    /// A used token was presented again, its whole family and the gift tokens issued to it were
+    /// revoked
+    Reused,
+    Invalid,
+}
+
+/// Swap a refresh token for a new one of the same family, each token can only be used once.
+/// Nothing is kept until `tx` is committed.
+async fn exchange(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token: &str,
+) -> Result<Exchange, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"SELECT family, gift, encrypted, expires_at, used_at, revoked_at
+        FROM gift_refresh_tokens WHERE hash = $1 FOR UPDATE"#,
+        hash(token)
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(Exchange::Invalid);
+    };
+    if row.revoked_at.is_some() || row.expires_at < Utc::now() {
+        return Ok(Exchange::Invalid);
+    }
+    if row.used_at.is_some() {
+        sqlx::query!(
+            r#"UPDATE gift_refresh_tokens SET revoked_at = now()
+            WHERE family = $1 AND revoked_at IS NULL"#,
+            row.family
+        )
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query!(
+            r#"UPDATE gift_tokens SET revoked_at = now() WHERE subject = $1 AND revoked_at IS NULL"#,
+            row.family.to_string()
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        return Ok(Exchange::Reused);
+    }
+
+    sqlx::query!(
+        r#"UPDATE gift_refresh_tokens SET used_at = now() WHERE hash = $1"#,
+        hash(token)
+    )
+    .execute(&mut **tx)
+    .await?;
+    let grant = Grant {
+        family: row.family,
+        gift: row.gift,
+        encrypted: row.encrypted,
+        expires_at: row.expires_at,
+    };
+    let token = issue(&mut **tx, &grant).await?;
+
+    Ok(Exchange::Rotated(grant, token))
+}
+
+/// Revoke the refresh tokens issued to `subject`
+pub(super) async fn revoke_subject(pool: &sqlx::PgPool, subject: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE gift_refresh_tokens SET revoked_at = now()
+        WHERE subject = $1 AND revoked_at IS NULL"#,
+        subject
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Forget refresh tokens past their expiry
+pub(super) async fn cleanup(pool: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM gift_refresh_tokens WHERE expires_at < now()"#)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Exchange the refresh cookie for a new gift token and refresh token. The rotation is only kept
+/// once the gift token was issued, so a failed refresh can be retried.
+pub(super) async fn refresh(State(state): State<GiftState>, headers: HeaderMap) -> Response {
+    debug!("Calling refresh");
+
+    let keys = &state.keys;
+    let token = match keys.cookie.get(&headers, Kind::Refresh) {
+        Ok(token) => token,
+        Err(status) => return status.into_response(),
+    };
+
+    let mut tx = match state.pool.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            warn!(?err, "Unable to exchange refresh token");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let (grant, refresh) = match exchange(&mut tx, &token).await {
+        Ok(Exchange::Rotated(grant, refresh)) => (grant, refresh),
+        Ok(Exchange::Reused) => {
+            if let Err(err) = tx.commit().await {
+                warn!(?err, "Unable to revoke refresh token family");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            warn!("Refresh token reused, revoked its family");
+            let challenge = r#"Bearer error="invalid_token", error_description="The refresh token was already used""#;
+            return (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, challenge)],
+            )
+                .into_response();
+        }
+        Ok(Exchange::Invalid) => return StatusCode::UNAUTHORIZED.into_response(),
+        Err(err) => {
+            warn!(?err, "Unable to exchange refresh token");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let (access, ttl) = match access_token(
+        &state,
+        grant.gift,
+        &grant.family.to_string(),
+        None,
+        grant.encrypted,
+    )
+    .await
+    {
+        Ok(access) => access,
+        Err(response) => return response,
+    };
+    if let Err(err) = tx.commit().await {
+        warn!(?err, "Unable to rotate refresh token");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    let refresh_ttl = (grant.expires_at - Utc::now()).num_seconds().max(0) as u64;
+
+    keys.cookie.set(
+        &headers,
+        vec![
+            (Kind::Access, access, ttl),
+            (Kind::Refresh, refresh, refresh_ttl),
+        ],
+    )
+}
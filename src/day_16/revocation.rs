@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use super::{authenticate, refresh, GiftState};
+
+/// How often tokens past their `exp` are forgotten
+pub(super) const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Remember an issued token so it can be revoked later
+pub(super) async fn record(
+    pool: &sqlx::PgPool,
+    jti: &str,
+    subject: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO gift_tokens (jti, subject, expires_at) VALUES ($1, $2, $3)"#,
+        jti,
+        subject,
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub(super) async fn is_revoked(pool: &sqlx::PgPool, jti: &str) -> Result<bool, sqlx::Error> {
+    let revoked = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM gift_tokens WHERE jti = $1 AND revoked_at IS NOT NULL) AS "revoked!""#,
+        jti
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(revoked)
+}
+
+/// Revoke an issued token by `jti`, if it is the caller's own token `own_jti` or was issued to
+/// the caller's `subject`
+async fn revoke_jti(
+    pool: &sqlx::PgPool,
+    jti: &str,
+    own_jti: &str,
+    subject: Option<&str>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE gift_tokens SET revoked_at = now()
+        WHERE jti = $1 AND revoked_at IS NULL AND (jti = $2 OR subject = $3)"#,
+        jti,
+        own_jti,
+        subject
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Revoke every gift token issued to `subject`
+async fn revoke_subject(pool: &sqlx::PgPool, subject: &str) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"UPDATE gift_tokens SET revoked_at = now() WHERE subject = $1 AND revoked_at IS NULL"#,
+        subject
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Forget tokens past their `exp`, they are refused anyway
+pub(super) async fn cleanup(pool: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(r#"DELETE FROM gift_tokens WHERE expires_at < now()"#)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Run `cleanup` and the refresh token cleanup every `CLEANUP_INTERVAL`
+pub(super) async fn cleanup_task(pool: sqlx::PgPool) {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match cleanup(&pool).await {
+            Ok(removed) => info!(removed, "Cleaned up gift tokens"),
+            Err(err) => warn!(?err, "Unable to clean up gift tokens"),
+        }
+        match refresh::cleanup(&pool).await {
+            Ok(removed) => info!(removed, "Cleaned up refresh tokens"),
+            Err(err) => warn!(?err, "Unable to clean up refresh tokens"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(super) struct Revoke {
+    jti: Option<String>,
+    sub: Option<String>,
+}
+
+/// Revoke a token or every token of a subject, only the caller's own as proven by their gift token
+///
+/// Only tokens issued by `/16/wrap` and `/16/refresh` are recorded. Elf tokens issued out of band
+/// are checked against the revocation list but can't be added to it, they stay valid until their
+/// `exp` unless the key that signed them is rotated out of `DAY16_KEYS`.
+pub(super) async fn revoke(
+    State(state): State<GiftState>,
+    headers: HeaderMap,
+    Json(revoke): Json<Revoke>,
+) -> Response {
+    debug!(?revoke, "Calling revoke");
+
+    if revoke.jti.is_some() == revoke.sub.is_some() {
+        return (StatusCode::BAD_REQUEST, "Expected one of `jti` or `sub`").into_response();
+    }
+    let claims = match authenticate(&state.pool, &state.keys, &headers).await {
+        Ok(Some(claims)) => claims,
+        Ok(None) => return StatusCode::UNAUTHORIZED.into_response(),
+        Err(response) => return response,
+    };
+    let own_jti = jti(&claims).unwrap_or_default();
+    let subject = claims.get("sub").and_then(Value::as_str);
+
+    let revoked = match (&revoke.jti, &revoke.sub) {
+        (Some(jti), _) => revoke_jti(&state.pool, jti, own_jti, subject).await,
+        (_, Some(sub)) if Some(sub.as_str()) == subject => {
+            match refresh::revoke_subject(&state.pool, sub).await {
+                Ok(_) => revoke_subject(&state.pool, sub).await,
+                Err(err) => Err(err),
+            }
+        }
+        _ => return StatusCode::FORBIDDEN.into_response(),
+    };
+
+    match revoked {
+        Ok(revoked) => Json(json!({ "revoked": revoked })).into_response(),
+        Err(err) => {
+            warn!(?err, "Unable to revoke");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// The `jti` of verified claims, if any
+pub(super) fn jti(claims: &Value) -> Option<&str> {
+    claims.get("jti").and_then(Value::as_str)
+}
@@ -1,5 +1,3 @@
-use std::u64;
-
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
@@ -129,7 +127,7 @@ async fn list(State(state): State<QuoteBookState>, page: Query<PaginationId>) ->
 
     if quotes.len() < 4 {
         Json(Page {
-            quotes: quotes,
+            quotes,
             page,
             next_token: None,
         })
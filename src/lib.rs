@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod day_00;
 pub mod day_02;
 pub mod day_05;
@@ -1,26 +1,49 @@
+use std::sync::Arc;
+
 use axum::{routing::get, Router};
-use shuttlings_cch24::{day_00, day_02, day_05, day_09, day_12, day_16, day_19, day_23};
+use shuttlings_cch24::{auth, day_00, day_02, day_05, day_09, day_12, day_16, day_19, day_23};
 use tower_http::services::ServeDir;
 
 #[shuttle_runtime::main]
 async fn main(
-    #[shuttle_shared_db::Postgres] pool: sqlx::PgPool
+    #[shuttle_shared_db::Postgres] pool: sqlx::PgPool,
+    #[shuttle_runtime::Secrets] secrets: shuttle_runtime::SecretStore,
 ) -> shuttle_axum::ShuttleAxum {
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
 
-    sqlx::migrate!().run(&pool).await.expect("Failed to run migrations");
+    day_16::spawn_cleanup(pool.clone());
+    let keys = Arc::new(day_16::KeyRing::load(
+        secrets.get("DAY16_KEYS"),
+        "config/day_16.toml",
+    ));
 
     let router = Router::new()
         .route("/", get(day_00::hello_world))
         .nest_service("/assets", ServeDir::new("assets"))
         .nest("/-1", day_00::router())
-        .nest("/2", day_02::router())
-        .nest("/5", day_05::router())
+        .nest(
+            "/2",
+            day_02::router(pool.clone(), secrets.get("DAY02_ADMIN_TOKEN")),
+        )
+        .nest(
+            "/5",
+            day_05::router(
+                pool.clone(),
+                day_05::Config::load("config/day_05.toml"),
+                secrets.get("DAY05_ADMIN_TOKEN"),
+            ),
+        )
         .nest_service("/9", day_09::router())
         .nest_service("/12", day_12::router())
-        .nest_service("/16", day_16::router())
+        .nest_service("/16", day_16::router(pool.clone(), keys.clone()))
+        .merge(day_16::well_known(keys.clone()))
         .nest_service("/19", day_19::router(pool.clone()))
         .nest_service("/23", day_23::router());
 
-    Ok(router.into())
-}
+    let auth = auth::Auth::new(auth::AuthConfig::load("config/auth.toml"), keys, pool);
 
+    Ok(auth::protect(router, auth).into())
+}